@@ -0,0 +1,33 @@
+# Example external parser for Korean using Kiwi (pip install kiwipiepy). See
+# the `[morph.external]` section of example/config/config.toml.
+#
+# Reads one JSON request per line from stdin and writes one JSON response per
+# line to stdout. Offsets are in characters.
+
+import json
+import sys
+
+from kiwipiepy import Kiwi
+
+
+kiwi = Kiwi()
+
+for line in sys.stdin:
+    try:
+        text = json.loads(line)["text"]
+        segments = []
+        for token in kiwi.tokenize(text):
+            lemma = token.form
+            if token.tag in ("VV", "VA", "VX", "VCP", "VCN"):
+                lemma += "다"
+            segments.append({
+                "start": token.start,
+                "end": token.start + token.len,
+                "lemma": lemma,
+                "pos": token.tag,
+                "debug": f"{token.form}/{token.tag} score={token.score:.2f}",
+            })
+        resp = {"segments": segments}
+    except Exception as e:
+        resp = {"error": str(e)}
+    print(json.dumps(resp, ensure_ascii=False), flush=True)
@@ -11,7 +11,8 @@ create table if not exists book (
   archived     boolean  not null default false,
   audio_file   varchar  check(audio_file <> ''),
   content_type varchar  not null check(content_type <> ''),
-  content      text     not null
+  content      text     not null,
+  language     varchar  check(language <> '')
 );
 create index if not exists book_added on book (added);
 create index if not exists book_published on book (published);
@@ -22,6 +23,31 @@ create table if not exists book_tag (
   tag     varchar not null check(tag <> '')
 );
 
+create table if not exists book_segmentation (
+  book_id  integer not null primary key references book(id) on delete cascade,
+  key      varchar not null check(key <> ''),
+  segments text    not null,
+  checked  text    not null default '{}'
+);
+
+create table if not exists parse_override (
+  id       integer not null primary key,
+  language varchar not null check(language <> ''),
+  word     varchar not null check(word <> ''),
+  parsing  varchar not null check(parsing <> '')
+);
+create index if not exists parse_override_word on parse_override (language, word);
+
+create table if not exists grammar_point (
+  id          integer not null primary key,
+  language    varchar not null check(language <> ''),
+  name        varchar not null check(name <> ''),
+  status      tinyint not null default 1 check(status <> 0),
+  explanation text    not null default '',
+  examples    text    not null default '',
+  unique (language, name)
+);
+
 begin;
 
 drop trigger if exists book_ai;
@@ -87,13 +113,14 @@ group by id;
 commit;
 
 create table if not exists word (
-  id             integer  not null primary key,
+  id             integer  not null primary key autoincrement,
   text           varchar  not null check(text <> ''),
   pronunciation  varchar  check(pronunciation <> ''),
   translation    text     not null,
   status         tinyint  default 1 check(status <> 0),
   added          datetime not null default 'now',
-  image_file     varchar  check(image_file <> '')
+  image_file     varchar  check(image_file <> ''),
+  language       varchar  check(language <> '')
 );
 create index if not exists word_text on word (text);
 create index if not exists word_added on word (added);
@@ -110,6 +137,38 @@ create table if not exists word_parent (
   primary key (child_word_id, parent_word_text)
 );
 
+create table if not exists review (
+  word_id       integer  not null primary key references word(id) on delete cascade,
+  due           datetime not null,
+  interval_days real     not null default 0,
+  ease          real     not null default 2.5,
+  repetitions   integer  not null default 0,
+  lapses        integer  not null default 0,
+  last_review   datetime
+);
+create index if not exists review_due on review (due);
+
+create table if not exists word_event (
+  id         integer  not null primary key,
+  word_id    integer  not null references word(id) on delete cascade,
+  time       datetime not null default current_timestamp,
+  kind       varchar  not null check(kind in ('status', 'tag_added', 'tag_removed')),
+  old_status tinyint,
+  new_status tinyint,
+  tag        varchar  check(tag <> '')
+);
+create index if not exists word_event_word on word_event (word_id);
+create index if not exists word_event_time on word_event (time);
+
+create table if not exists word_revision (
+  id       integer  not null primary key,
+  word_id  integer  not null,
+  time     datetime not null default current_timestamp,
+  deleted  boolean  not null default false,
+  snapshot text     not null
+);
+create index if not exists word_revision_word on word_revision (word_id);
+
 begin;
 
 drop trigger if exists word_ai;
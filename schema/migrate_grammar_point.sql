@@ -0,0 +1,12 @@
+-- Adds the `grammar_point` table, which holds grammar points that are tracked
+-- like words. Term pattern rules refer to grammar points by name.
+
+create table if not exists grammar_point (
+  id          integer not null primary key,
+  language    varchar not null check(language <> ''),
+  name        varchar not null check(name <> ''),
+  status      tinyint not null default 1 check(status <> 0),
+  explanation text    not null default '',
+  examples    text    not null default '',
+  unique (language, name)
+);
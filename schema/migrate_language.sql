@@ -0,0 +1,9 @@
+-- Adds the `language` column to books and words. A null language refers to
+-- the default language (`lang`) from the configuration file.
+
+begin;
+
+alter table book add column language varchar check(language <> '');
+alter table word add column language varchar check(language <> '');
+
+commit;
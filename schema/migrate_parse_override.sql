@@ -0,0 +1,11 @@
+-- Adds the `parse_override` table, which holds corrections to how words are
+-- parsed, entered from the reader. These take precedence over the overrides
+-- file in the configuration.
+
+create table if not exists parse_override (
+  id       integer not null primary key,
+  language varchar not null check(language <> ''),
+  word     varchar not null check(word <> ''),
+  parsing  varchar not null check(parsing <> '')
+);
+create index if not exists parse_override_word on parse_override (language, word);
@@ -0,0 +1,13 @@
+-- Adds the `review` table, which holds the spaced-repetition schedule (SM-2)
+-- of each word that has been reviewed.
+
+create table if not exists review (
+  word_id       integer  not null primary key references word(id) on delete cascade,
+  due           datetime not null,
+  interval_days real     not null default 0,
+  ease          real     not null default 2.5,
+  repetitions   integer  not null default 0,
+  lapses        integer  not null default 0,
+  last_review   datetime
+);
+create index if not exists review_due on review (due);
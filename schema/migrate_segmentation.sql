@@ -0,0 +1,8 @@
+-- Adds the `book_segmentation` table, which caches the parser output for each
+-- book. Rows are replaced whenever the book text or parser changes.
+
+create table if not exists book_segmentation (
+  book_id  integer not null primary key references book(id) on delete cascade,
+  key      varchar not null check(key <> ''),
+  segments text    not null
+);
@@ -0,0 +1,11 @@
+-- Adds the `checked` column to `book_segmentation`, recording which texts the
+-- parser looked up in the dictionary (and whether they existed), so that a
+-- cached segmentation is only discarded when one of those words is added or
+-- removed. This replaces the global `word_generation` counter.
+
+alter table book_segmentation add column checked text not null default '{}';
+
+drop trigger if exists word_generation_ai;
+drop trigger if exists word_generation_ad;
+drop trigger if exists word_generation_au;
+drop table if exists word_generation;
@@ -0,0 +1,43 @@
+-- Makes word IDs autoincrement, so that the ID of a deleted word is never given
+-- to a new word. Revisions (`word_revision`) are kept by word ID after a word
+-- is deleted, and would otherwise be mixed up with those of the new word. IDs
+-- up to the highest one with a revision are skipped, too.
+--
+-- Dropping the old table drops the search index triggers, so run
+-- `schema/db.sql` again afterwards to recreate them.
+
+pragma foreign_keys = off;
+
+begin;
+
+create table word_new (
+  id             integer  not null primary key autoincrement,
+  text           varchar  not null check(text <> ''),
+  pronunciation  varchar  check(pronunciation <> ''),
+  translation    text     not null,
+  status         tinyint  default 1 check(status <> 0),
+  added          datetime not null default 'now',
+  image_file     varchar  check(image_file <> ''),
+  language       varchar  check(language <> '')
+);
+
+insert into word_new (id, text, pronunciation, translation, status, added, image_file, language)
+select id, text, pronunciation, translation, status, added, image_file, language
+from word;
+
+insert into sqlite_sequence (name, seq)
+select 'word_new', 0
+where not exists (select 1 from sqlite_sequence where name = 'word_new');
+update sqlite_sequence
+set seq = max(seq, (select coalesce(max(word_id), 0) from word_revision))
+where name = 'word_new';
+
+drop table word;
+alter table word_new rename to word;
+
+create index word_text on word (text);
+create index word_added on word (added);
+
+commit;
+
+pragma foreign_keys = on;
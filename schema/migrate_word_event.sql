@@ -0,0 +1,14 @@
+-- Adds the `word_event` table, which records changes to the status and tags of
+-- words. Changes made before this table was added are not known.
+
+create table if not exists word_event (
+  id         integer  not null primary key,
+  word_id    integer  not null references word(id) on delete cascade,
+  time       datetime not null default current_timestamp,
+  kind       varchar  not null check(kind in ('status', 'tag_added', 'tag_removed')),
+  old_status tinyint,
+  new_status tinyint,
+  tag        varchar  check(tag <> '')
+);
+create index if not exists word_event_word on word_event (word_id);
+create index if not exists word_event_time on word_event (time);
@@ -0,0 +1,12 @@
+-- Adds the `word_revision` table, which holds a snapshot of each word every
+-- time it is saved or deleted, so that earlier versions can be restored. The
+-- word ID isn't a foreign key, since revisions of deleted words are kept.
+
+create table if not exists word_revision (
+  id       integer  not null primary key,
+  word_id  integer  not null,
+  time     datetime not null default current_timestamp,
+  deleted  boolean  not null default false,
+  snapshot text     not null
+);
+create index if not exists word_revision_word on word_revision (word_id);
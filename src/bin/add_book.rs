@@ -39,6 +39,9 @@ struct Args {
     #[arg(short='A', long, help="indicates whether the book is archived")]
     archived: bool,
 
+    #[arg(short, long, help="language of the book (defaults to `lang` from the configuration file)")]
+    language: Option<String>,
+
     #[arg(long, help="path to the audio file for the book")]
     audio: Option<String>,
 
@@ -75,6 +78,7 @@ struct Args {
             "audio_file",
             "content_type",
             "content",
+            "language",
             "tags",
         ],
     )]
@@ -95,6 +99,7 @@ struct Args {
             "audio_file",
             "content_type",
             "content",
+            "language",
             "tags",
         ],
     )]
@@ -149,6 +154,9 @@ async fn main() -> Result<()> {
     }
 
     let config = Config::load(&args.config)?;
+    if let Some(ref language) = args.language {
+        config.language(Some(language))?;
+    }
     tokio::fs::create_dir_all(config.word_images_path()).await?;
     tokio::fs::create_dir_all(config.book_audio_path()).await?;
 
@@ -310,13 +318,16 @@ async fn main() -> Result<()> {
         if update_field(&mut query, "content", &args) {
             query.push_bind(text);
         }
+        if update_field(&mut query, "language", &args) {
+            query.push_bind(args.language.clone());
+        }
         query.push(" WHERE id = ");
         query.push_bind(id);
         query.build().execute(&mut *txn).await?;
     } else { // insert
         let result = sqlx::query("
-            INSERT INTO book (title, slug, url, added, published, last_read, archived, audio_file, content_type, content)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO book (title, slug, url, added, published, last_read, archived, audio_file, content_type, content, language)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ")
             .bind(title)
             .bind(args.slug.clone())
@@ -328,6 +339,7 @@ async fn main() -> Result<()> {
             .bind(audio)
             .bind(content_type)
             .bind(text)
+            .bind(args.language.clone())
             .execute(&mut *txn)
             .await?;
         existing_id = Some(result.last_insert_rowid());
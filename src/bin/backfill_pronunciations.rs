@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use dialoguer::Confirm;
+use lit::{config::Config, morph::{korean::KoreanPronunciation, MorphConfig}};
+
+#[derive(Parser, Debug)]
+#[command(about = "Fills in missing pronunciations of Korean words in the dictionary")]
+struct Args {
+    #[arg(long, help="path to config file")]
+    config: String,
+
+    #[arg(short, long, help="language of the words to update (defaults to `lang` from the configuration file)")]
+    language: Option<String>,
+
+    #[arg(long, help="replace existing pronunciations as well as missing ones")]
+    overwrite: bool,
+
+    #[arg(long, short='y', help="commit changes without asking for confirmation")]
+    yes: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = Config::load(&args.config)?;
+    let (lang, lang_config) = config.language(args.language.as_deref())?;
+    let MorphConfig::Korean(ref korean) = lang_config.morph else {
+        return Err(anyhow!("language {lang} does not use the Korean parser"));
+    };
+    if korean.pronunciation == KoreanPronunciation::Off {
+        return Err(anyhow!("pronunciation is turned off for language {lang}"));
+    }
+    let pool = config.database.open().await?;
+
+    let words: Vec<(i64, String, Option<String>)> = sqlx::query_as("
+        SELECT id, text, pronunciation
+        FROM word
+        WHERE language = ? OR (? AND language IS NULL)
+        ORDER BY id
+        ")
+        .bind(lang)
+        .bind(lang == config.lang)
+        .fetch_all(&pool)
+        .await?;
+
+    let mut updates = vec![];
+    for (id, text, old) in words {
+        if old.is_some() && !args.overwrite {
+            continue;
+        }
+        let Some(new) = korean.pronunciation.pronounce(&text) else {
+            continue;
+        };
+        if old.as_ref() == Some(&new) {
+            continue;
+        }
+        match old {
+            Some(old) => println!("{id}: {text}: {old} => {new}"),
+            None => println!("{id}: {text}: {new}"),
+        }
+        updates.push((id, new));
+    }
+
+    if updates.is_empty() {
+        println!("No pronunciations to update");
+        return Ok(());
+    }
+
+    println!();
+    println!("Will update {} words", updates.len());
+    if !args.yes && !Confirm::new().with_prompt("Commit?").wait_for_newline(true).interact()? {
+        println!("Not committing changes");
+        return Ok(());
+    }
+
+    let mut txn = pool.begin().await?;
+    for (id, pronunciation) in updates {
+        sqlx::query("UPDATE word SET pronunciation = ? WHERE id = ?")
+            .bind(pronunciation)
+            .bind(id)
+            .execute(&mut *txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    Ok(())
+}
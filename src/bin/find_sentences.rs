@@ -1,17 +1,16 @@
-use std::{cmp::Ordering, collections::{BTreeMap, VecDeque}, pin::Pin, sync::Arc};
+use std::{pin::Pin, sync::Arc};
 
 use anyhow::anyhow;
 use async_stream::try_stream;
-use clap::Parser;
+use clap::{ArgGroup, Parser};
 use futures::{Stream, StreamExt};
 use indicatif::ProgressBar;
-use itertools::Itertools;
-use lit::{bad_req, books::{Book, Books}, config::Config, dict::{Dictionary, WordStatus}, doc::{self, Document}, morph::{self, analyze_document, Morph, Segment}, Result};
+use lit::{bad_req, books::{Book, Books}, config::Config, dict::{Dictionary, WordStatus}, doc::{self, Document}, morph::{cache::SegmentCache, Morphs}, search::{compare_readability, find_sentences, SearchParams, SearchTarget, Sentence}, Result};
 use tokio::task::JoinSet;
-use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(group(ArgGroup::new("target").required(true).args(["target_word", "grammar", "tag"])))]
 struct Args {
     #[arg(short, long, help="the ID of a single book to analyze")]
     book: Option<i64>,
@@ -29,7 +28,13 @@ struct Args {
     concurrency: usize,
 
     #[arg(short, long, help="word to search for")]
-    target_word: String,
+    target_word: Option<String>,
+
+    #[arg(short, long, help="grammar point to search for, as named in the term patterns")]
+    grammar: Option<String>,
+
+    #[arg(long, help="tag to search for, e.g., one added by a term pattern rule")]
+    tag: Option<String>,
 
     #[arg(short='M', long, help="maximum number of words in target sentences", default_value_t=21)]
     max_sentence_words: usize,
@@ -49,8 +54,18 @@ async fn main() -> Result<()> {
     let books = Books::new(pool.clone(), config.book_audio_path());
     let dict = Dictionary::new(pool.clone(), config.word_images_path());
     dict.prefetch_all().await?;
-    let lang = Morph::load(&config.morph, dict.clone())?;
-    let lang = Arc::new(lang);
+    let morphs = Arc::new(Morphs::load(&config, dict.clone(), SegmentCache::new(pool.clone()))?);
+    let target = match (args.target_word.clone(), args.grammar.clone(), args.tag.clone()) {
+        (Some(word), _, _) => SearchTarget::Word(word),
+        (_, Some(grammar), _) => SearchTarget::Grammar(grammar),
+        (_, _, Some(tag)) => SearchTarget::Tag(tag),
+        _ => unreachable!("clap requires a target"),
+    };
+    let params = Arc::new(SearchParams {
+        target,
+        min_sentence_words: args.min_sentence_words,
+        max_sentence_words: args.max_sentence_words,
+    });
 
     let mut books_stream = book_list(&books, &args);
     let mut books = vec![];
@@ -81,7 +96,7 @@ async fn main() -> Result<()> {
             sentences.extend(doc_sentences.clone().into_iter().map(|s| (doc_info.clone(), s)));
             progress.inc(1);
         }
-        tasks.spawn(analyze_book(args.target_word.clone(), args.min_sentence_words, args.max_sentence_words, book, dict.clone(), lang.clone()));
+        tasks.spawn(analyze_book(params.clone(), book, dict.clone(), morphs.clone()));
     }
     while let Some(result) = tasks.join_next().await {
         let doc: Document = result??;
@@ -136,7 +151,7 @@ fn book_list<'a>(books: &'a Books, args: &'a Args) -> Pin<Box<dyn Stream<Item =
     Box::pin(books.all_books())
 }
 
-async fn analyze_book(target_word: String, min_sentence_words: usize, max_sentence_words: usize, book: Book, dict: Dictionary, lang: Arc<impl morph::Parser>) -> Result<Document> {
+async fn analyze_book(params: Arc<SearchParams>, book: Book, dict: Dictionary, morphs: Arc<Morphs>) -> Result<Document> {
     let parser: Box<dyn doc::Parser> = match book.content_type.as_str() {
         "text/plain" => Box::new(doc::PlainTextParser),
         "text/vtt" => Box::new(doc::vtt::VttParser),
@@ -144,10 +159,12 @@ async fn analyze_book(target_word: String, min_sentence_words: usize, max_senten
         t => return bad_req(format!("invalid book content type: {t}").as_str()),
     };
 
+    let (book_id, language) = (book.id, book.language.clone());
     let document = parser.parse_document(&book.content).map_err(|e| anyhow!("cannot parse book {}: {e}", book.id))?.with(book);
-    let document = analyze_document(document, &*lang, &dict).await?;
-    let document = compute_document_stats(&target_word, min_sentence_words, max_sentence_words, &dict, document).await?;
-    Ok(document)
+    let dict = dict.for_language(morphs.language(language.as_deref()));
+    let document = morphs.analyze_book(book_id, language.as_deref(), document, &dict).await?;
+    let sentences = find_sentences(&params, &dict, &document).await?;
+    Ok(document.with(sentences))
 }
 
 #[derive(Clone, Debug, Default)]
@@ -157,116 +174,3 @@ struct DocumentInfo {
     url: Option<String>,
     slug: Option<String>,
 }
-
-struct SearchParams<'a> {
-    target_word: &'a str,
-    max_sentence_words: usize,
-}
-
-#[derive(Clone, Debug, Default)]
-struct Sentence {
-    words: Vec<SentenceWord>,
-}
-
-#[derive(Clone, Debug, Default)]
-struct SentenceWord {
-    text: String,
-    status: WordStatus,
-    is_target: bool,
-}
-
-fn min_non_target_status(s: &Sentence) -> Option<WordStatus> {
-    let mut status = None;
-    for word in s.words.iter().filter(|w| !w.is_target) {
-        status = match (status, word.status) {
-            (st, WordStatus::Ignored) => st,
-            (None, st) => Some(st),
-            (Some(a), b) => Some(a.min(b))
-        }
-    }
-    status
-}
-
-fn compare_readability(a: &Sentence, b: &Sentence) -> Ordering {
-    match (min_non_target_status(a), min_non_target_status(b)) {
-        (None, None) => return Ordering::Equal,
-        (Some(_), None) => return Ordering::Greater,
-        (None, Some(_)) => return Ordering::Less,
-        (Some(x), Some(y)) if x == y => x,
-        (Some(x), Some(y)) => return x.cmp(&y),
-    };
-
-    for status in [WordStatus::Unknown, WordStatus::New, WordStatus::Level2, WordStatus::Level3, WordStatus::Level4, WordStatus::Level5, WordStatus::WellKnown] {
-        let a_count = a.words.iter().filter(|w| !w.is_target && w.status == status).count();
-        let b_count = b.words.iter().filter(|w| !w.is_target && w.status == status).count();
-        if a_count < b_count {
-            return Ordering::Greater;
-        } else if a_count > b_count {
-            return Ordering::Less;
-        }
-    }
-
-    a.words.len().cmp(&b.words.len())
-}
-
-async fn compute_document_stats(target_word: &str, min_sentence_words: usize, max_sentence_words: usize, dict: &Dictionary, doc: Document) -> Result<Document> {
-    let words: &BTreeMap<usize, Segment> = doc.info()
-        .ok_or_else(|| anyhow!("could not analyze document"))?;
-    let mut sentences = vec![];
-
-    for (start, sentence) in doc.text.split_sentence_bound_indices() {
-        let mut context_words = VecDeque::new();
-        let end = start + sentence.len();
-        for (_, seg) in words.range(start..end) {
-            let (_, optimistic_rating) =
-                dict.resolve_stati(seg.words.iter()).await
-                .unwrap_or((WordStatus::Unknown, WordStatus::Unknown));
-            let is_target_word = seg.text == target_word;
-            let mut all_parents = vec![];
-            for word in seg.words.iter() {
-                all_parents.extend(word.parents.clone());
-            }
-            let derived_from_target =
-                dict.find_word_trees_by_text(all_parents).await?
-                .contains_key(target_word);
-            context_words.push_back(SentenceWord {
-                text: seg.text.to_string(),
-                status: optimistic_rating,
-                is_target: is_target_word || derived_from_target,
-            });
-            if context_words.len() > max_sentence_words {
-                context_words.pop_front();
-                let mid = max_sentence_words / 2;
-                if context_words[mid].is_target { // FIXME: off-by-one?
-                    let words = context_words.iter().cloned().collect_vec();
-                    sentences.push(Sentence { words });
-                }
-            } else if context_words.len() == max_sentence_words {
-                let mid = max_sentence_words / 2;
-                if context_words.iter().take(mid).any(|w| w.is_target) { // FIXME: off-by-one?
-                    let words = context_words.iter().cloned().collect_vec();
-                    sentences.push(Sentence { words });
-                }
-            }
-        }
-
-        if context_words.len() < max_sentence_words {
-            if context_words.len() >= min_sentence_words {
-                if context_words.iter().any(|w| w.is_target) {
-                    let words = context_words.iter().cloned().collect_vec();
-                    sentences.push(Sentence { words });
-                }
-            }
-        } else {
-            let mid = max_sentence_words / 2;
-            if context_words.iter().dropping(mid).any(|w| w.is_target) { // FIXME: off-by-one?
-                let words = context_words.iter().cloned().collect_vec();
-                sentences.push(Sentence { words });
-            }
-        }
-
-
-    }
-
-    Ok(doc.with(sentences))
-}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use clap::Parser;
+use lit::{config::Config, dict::Dictionary, morph::{korean::KoreanParser, MorphConfig}, overrides::ParseOverrides, Result};
+
+#[derive(Parser, Debug)]
+#[command(about = "Checks the Korean term patterns and overrides files for problems")]
+struct Args {
+    #[arg(long, help="path to config file")]
+    config: String,
+
+    #[arg(short, long, help="language whose patterns to check (defaults to `lang` from the configuration file)")]
+    language: Option<String>,
+
+    #[arg(short, long, help="CSV file of words with their expected parents, tags, and translation")]
+    golden: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = Config::load(&args.config)?;
+    let (lang, lang_config) = config.language(args.language.as_deref())?;
+    let MorphConfig::Korean(ref korean) = lang_config.morph else {
+        return Err(anyhow!("language {lang} does not use the Korean parser").into());
+    };
+
+    let mut issues = korean.lint()?;
+    if let Some(ref golden) = args.golden {
+        let pool = config.database.open().await?;
+        let dict = Dictionary::new(pool.clone(), config.word_images_path());
+        dict.prefetch_all().await?;
+        let parser = KoreanParser::load(korean, dict.for_language(lang))?;
+        parser.set_overrides(&ParseOverrides::new(pool.clone()).list(lang, None).await?);
+        issues.extend(parser.check_golden(golden).await?);
+    }
+
+    for issue in issues.iter() {
+        println!("{issue}");
+    }
+    if !issues.is_empty() {
+        println!();
+        println!("{} problems found", issues.len());
+        std::process::exit(1);
+    }
+    println!("No problems found");
+    Ok(())
+}
@@ -5,7 +5,7 @@ use async_stream::try_stream;
 use clap::Parser;
 use futures::{Stream, StreamExt};
 use indicatif::ProgressBar;
-use lit::{bad_req, books::{Book, Books}, config::Config, dict::{Dictionary, Word, WordStatus}, doc::{self, Document}, morph::{self, analyze_document, Morph, Segment}, Result};
+use lit::{bad_req, books::{Book, Books}, config::Config, dict::{Dictionary, Word, WordStatus}, doc::{self, Document}, morph::{cache::SegmentCache, Morphs, Segment}, Result};
 use tokio::task::JoinSet;
 
 #[derive(Parser, Debug)]
@@ -35,8 +35,7 @@ async fn main() -> Result<()> {
     let books = Books::new(pool.clone(), config.book_audio_path());
     let dict = Dictionary::new(pool.clone(), config.word_images_path());
     dict.prefetch_all().await?;
-    let lang = Morph::load(&config.morph, dict.clone())?;
-    let lang = Arc::new(lang);
+    let morphs = Arc::new(Morphs::load(&config, dict.clone(), SegmentCache::new(pool.clone()))?);
 
     let mut books_stream = book_list(&books, &args);
     let mut books = vec![];
@@ -57,7 +56,7 @@ async fn main() -> Result<()> {
             stats.push(result);
             progress.inc(1);
         }
-        tasks.spawn(analyze_book(book, dict.clone(), lang.clone()));
+        tasks.spawn(analyze_book(book, dict.clone(), morphs.clone()));
     }
     while let Some(result) = tasks.join_next().await {
         let result = result??;
@@ -99,7 +98,7 @@ fn book_list<'a>(books: &'a Books, args: &'a Args) -> Pin<Box<dyn Stream<Item =
     Box::pin(books.all_books())
 }
 
-async fn analyze_book(book: Book, dict: Dictionary, lang: Arc<impl morph::Parser>) -> Result<DocumentStats> {
+async fn analyze_book(book: Book, dict: Dictionary, morphs: Arc<Morphs>) -> Result<DocumentStats> {
     let parser: Box<dyn doc::Parser> = match book.content_type.as_str() {
         "text/plain" => Box::new(doc::PlainTextParser),
         "text/vtt" => Box::new(doc::vtt::VttParser),
@@ -107,8 +106,10 @@ async fn analyze_book(book: Book, dict: Dictionary, lang: Arc<impl morph::Parser
         t => return bad_req(format!("invalid book content type: {t}").as_str()),
     };
 
+    let (book_id, language) = (book.id, book.language.clone());
     let document = parser.parse_document(&book.content).map_err(|e| anyhow!("cannot parse book {}: {e}", book.id))?.with(book);
-    let document = analyze_document(document, &*lang, &dict).await?;
+    let dict = dict.for_language(morphs.language(language.as_deref()));
+    let document = morphs.analyze_book(book_id, language.as_deref(), document, &dict).await?;
     let document = compute_document_stats(&dict, document).await?;
     Ok(document.info().cloned().unwrap())
 }
@@ -6,7 +6,7 @@ use clap::Parser;
 use futures::{Stream, StreamExt};
 use indicatif::ProgressBar;
 use itertools::Itertools;
-use lit::{bad_req, books::{Book, Books}, config::Config, dict::{Dictionary, WordStatus}, doc::{self, Document}, morph::{self, analyze_document, Morph, Segment}, Result};
+use lit::{bad_req, books::{Book, Books}, config::Config, dict::{Dictionary, WordStatus}, doc::{self, Document}, morph::{cache::SegmentCache, Morphs, Segment}, Result};
 use tokio::task::JoinSet;
 
 #[derive(Parser, Debug)]
@@ -39,8 +39,7 @@ async fn main() -> Result<()> {
     let books = Books::new(pool.clone(), config.book_audio_path());
     let dict = Dictionary::new(pool.clone(), config.word_images_path());
     dict.prefetch_all().await?;
-    let lang = Morph::load(&config.morph, dict.clone())?;
-    let lang = Arc::new(lang);
+    let morphs = Arc::new(Morphs::load(&config, dict.clone(), SegmentCache::new(pool.clone()))?);
     let max_status = match args.max_status {
         Some(0) => WordStatus::Unknown, 
         Some(1) => WordStatus::New,
@@ -71,7 +70,7 @@ async fn main() -> Result<()> {
             merge_roots(&mut roots, result.roots);
             progress.inc(1);
         }
-        tasks.spawn(analyze_book(max_status, book, dict.clone(), lang.clone()));
+        tasks.spawn(analyze_book(max_status, book, dict.clone(), morphs.clone()));
     }
     while let Some(result) = tasks.join_next().await {
         let result = result??;
@@ -131,7 +130,7 @@ fn book_list<'a>(books: &'a Books, args: &'a Args) -> Pin<Box<dyn Stream<Item =
     Box::pin(books.all_books())
 }
 
-async fn analyze_book(max_status: WordStatus, book: Book, dict: Dictionary, lang: Arc<impl morph::Parser>) -> Result<DocumentStats> {
+async fn analyze_book(max_status: WordStatus, book: Book, dict: Dictionary, morphs: Arc<Morphs>) -> Result<DocumentStats> {
     let parser: Box<dyn doc::Parser> = match book.content_type.as_str() {
         "text/plain" => Box::new(doc::PlainTextParser),
         "text/vtt" => Box::new(doc::vtt::VttParser),
@@ -139,8 +138,10 @@ async fn analyze_book(max_status: WordStatus, book: Book, dict: Dictionary, lang
         t => return bad_req(format!("invalid book content type: {t}").as_str()),
     };
 
+    let (book_id, language) = (book.id, book.language.clone());
     let document = parser.parse_document(&book.content).map_err(|e| anyhow!("cannot parse book {}: {e}", book.id))?.with(book);
-    let document = analyze_document(document, &*lang, &dict).await?;
+    let dict = dict.for_language(morphs.language(language.as_deref()));
+    let document = morphs.analyze_book(book_id, language.as_deref(), document, &dict).await?;
     let document = compute_document_stats(max_status, &dict, document).await?;
     Ok(document.info().cloned().unwrap())
 }
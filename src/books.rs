@@ -21,6 +21,7 @@ pub struct Book {
     pub url: Option<String>,
     pub published: Option<DateTime<chrono::Utc>>,
     pub last_read: Option<DateTime<chrono::Local>>,
+    pub language: Option<String>,
 }
 
 #[derive(Clone)]
@@ -81,6 +82,18 @@ impl Books {
         Ok(())
     }
 
+    pub async fn set_book_language(&self, id: i64, language: Option<&str>) -> Result<()> {
+        let result = sqlx::query("UPDATE book SET language = ? WHERE id = ?")
+            .bind(language.filter(|s| !s.is_empty()))
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() != 1 {
+            not_found()?;
+        }
+        Ok(())
+    }
+
     pub async fn set_book_content(&self, id: i64, content: &str) -> Result<()> {
         let result = sqlx::query("UPDATE book SET content = ? WHERE id = ?")
             .bind(content)
@@ -99,6 +112,14 @@ impl Books {
             .map_err(move |err| anyhow!("failed reading book: {err}").into())
     } 
 
+    /// Like `all_books`, but the most recently read books first, followed by
+    /// the books never read, newest first.
+    pub fn recently_read_books(&self) -> impl Stream<Item = Result<Book>> + use<'_> {
+        sqlx::query_as("SELECT * FROM book ORDER BY last_read IS NULL, last_read DESC, added DESC")
+            .fetch(&self.db)
+            .map_err(move |err| anyhow!("failed reading book: {err}").into())
+    }
+
     pub fn search_books(&self, filter: String) -> impl Stream<Item = Result<Book>> + use<'_> {
         sqlx::query_as("
             SELECT *
@@ -119,6 +140,7 @@ pub struct NewBook {
     pub audio_file: Option<String>,
     pub url: Option<String>,
     pub published: Option<DateTime<chrono::Utc>>,
+    pub language: Option<String>,
     pub tags: Vec<String>,
 }
 
@@ -126,8 +148,8 @@ impl Books {
     pub async fn insert_book(&self, book: NewBook) -> Result<i64> {
         let mut txn = self.db.begin().await?;
         let result = sqlx::query("
-            INSERT INTO book (slug, title, content_type, content, audio_file, url, published)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO book (slug, title, content_type, content, audio_file, url, published, language)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
         ")
             .bind(book.slug)
             .bind(book.title)
@@ -136,6 +158,7 @@ impl Books {
             .bind(book.audio_file)
             .bind(book.url)
             .bind(book.published)
+            .bind(book.language)
             .execute(&mut *txn)
             .await?;
         let id = result.last_insert_rowid();
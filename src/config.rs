@@ -1,4 +1,4 @@
-use std::{collections::{HashMap, HashSet}, path::PathBuf};
+use std::{collections::{BTreeMap, HashMap, HashSet}, path::PathBuf};
 
 use anyhow::{anyhow, Result};
 use figment::{providers::{Format, Toml}, value::magic::RelativePathBuf, Figment};
@@ -38,6 +38,15 @@ pub struct ImportPluginConfig {
     pub clean_vtt: Option<CleanVttOptions>,
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LanguageConfig {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub dictionaries: Vec<Dictionary>,
+    #[serde(default)]
+    pub morph: MorphConfig,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
     pub port: u16,
@@ -45,10 +54,13 @@ pub struct Config {
     pub lang: String,
     #[serde(default)]
     pub display: DisplayConfig,
+    #[serde(default)]
     pub dictionaries: Vec<Dictionary>,
     #[serde(default)]
     pub morph: MorphConfig,
     #[serde(default)]
+    pub languages: BTreeMap<String, LanguageConfig>,
+    #[serde(default)]
     pub template: TemplateConfig,
     userdata: RelativePathBuf,
     #[serde(default)]
@@ -57,13 +69,30 @@ pub struct Config {
 
 impl Config {
     pub fn load(path: &str) -> Result<Self> {
-        let config: Config = Figment::from(Toml::file(path)).extract()?;
+        let mut config: Config = Figment::from(Toml::file(path)).extract()?;
         if !config.userdata.relative().is_absolute() {
             Err(anyhow!("`userdata` must resolve to an absolute path"))?;
         }
+        if !config.languages.contains_key(&config.lang) {
+            let default = LanguageConfig {
+                name: None,
+                dictionaries: config.dictionaries.clone(),
+                morph: config.morph.clone(),
+            };
+            config.languages.insert(config.lang.clone(), default);
+        }
         Ok(config)
     }
 
+    /// Returns the code and configuration for the given language, or for the
+    /// default language (`lang`) if none is given.
+    pub fn language<'a>(&'a self, lang: Option<&'a str>) -> Result<(&'a str, &'a LanguageConfig)> {
+        let lang = lang.unwrap_or(&self.lang);
+        let config = self.languages.get(lang)
+            .ok_or_else(|| anyhow!("language not configured: {lang}"))?;
+        Ok((lang, config))
+    }
+
     pub fn userdata(&self) -> PathBuf {
         self.userdata.relative()
     }
@@ -1,15 +1,20 @@
-use std::{collections::{HashMap, VecDeque}, path::PathBuf, sync::Arc};
+use std::{cell::RefCell, collections::{BTreeMap, HashMap, HashSet, VecDeque}, future::Future, path::PathBuf, sync::Arc};
 
 use anyhow::anyhow;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use serde_with::skip_serializing_none;
-use sqlx::{Pool, Sqlite};
+use sqlx::{Pool, Sqlite, SqliteConnection};
 use tokio::sync::RwLock;
 
 use crate::{bad_req, check, dt, must, not_found, Result};
 
+pub mod bulk;
+pub mod history;
+pub mod review;
+pub mod revision;
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize_repr, Deserialize_repr, sqlx::Type)]
 #[repr(u8)]
 pub enum WordStatus {
@@ -35,6 +40,7 @@ pub struct Word {
     pub tags: Vec<String>,
     pub parents: Vec<String>,
     pub image_file: Option<String>,
+    pub language: Option<String>,
 
     #[serde(skip_deserializing)]
     pub resolved_status: Option<(WordStatus, WordStatus)>,
@@ -42,6 +48,14 @@ pub struct Word {
     pub inherit: bool,
     #[serde(skip_deserializing)]
     pub debug: Option<String>,
+    /// The Hanja spelling of a Sino-Korean word, if known. This isn't stored,
+    /// but filled in from the parser's Hanja dictionary.
+    #[serde(skip_deserializing)]
+    pub hanja: Option<String>,
+    /// The grammar points (see `crate::grammar`) used in the word, as found by
+    /// the parser. Like `hanja`, this isn't stored.
+    #[serde(skip_deserializing)]
+    pub grammar: Vec<String>,
 }
 
 pub const EMPTY_WORD: Word = Word {
@@ -54,15 +68,37 @@ pub const EMPTY_WORD: Word = Word {
     tags: vec![],
     parents: vec![],
     image_file: None,
+    language: None,
     debug: None,
+    hanja: None,
+    grammar: vec![],
     resolved_status: None,
 };
 
+tokio::task_local! {
+    /// The texts checked with `Dictionary::word_exists` inside
+    /// `track_checked_words`, with whether they existed.
+    static CHECKED_WORDS: RefCell<BTreeMap<String, bool>>;
+}
+
+/// Runs the future, returning its output along with the texts it checked with
+/// `Dictionary::word_exists` and whether they existed at the time. This tells
+/// which dictionary changes could change the output of a parser.
+pub async fn track_checked_words<F: Future>(future: F) -> (F::Output, BTreeMap<String, bool>) {
+    CHECKED_WORDS.scope(RefCell::default(), async move {
+        let output = future.await;
+        (output, CHECKED_WORDS.with(|checked| checked.take()))
+    }).await
+}
+
 #[derive(Clone)]
 pub struct Dictionary {
     word_images_path: PathBuf,
     db: Pool<Sqlite>,
     cache: Arc<RwLock<Cache>>,
+    /// The language that word lookups are limited to, if any. Words without
+    /// a language are found in every language.
+    language: Option<String>,
 }
 
 #[derive(Debug, Default)]
@@ -196,6 +232,7 @@ struct DbWord {
     pronunciation: Option<String>,
     translation: String,
     image_file: Option<String>,
+    language: Option<String>,
 }
 
 impl Dictionary {
@@ -203,6 +240,20 @@ impl Dictionary {
         Self {
             db, word_images_path,
             cache: Arc::new(RwLock::new(Cache::new())),
+            language: None,
+        }
+    }
+
+    /// Returns a dictionary sharing this one's database and cache, whose word
+    /// lookups only find words in the given language (or without a language).
+    pub fn for_language(&self, language: &str) -> Self {
+        Self { language: Some(language.to_string()), ..self.clone() }
+    }
+
+    fn in_language(&self, word: &Word) -> bool {
+        match (&self.language, &word.language) {
+            (Some(language), Some(word_language)) => language == word_language,
+            _ => true,
         }
     }
 
@@ -235,9 +286,12 @@ impl Dictionary {
                 pronunciation: wr.pronunciation,
                 translation: wr.translation,
                 image_file: wr.image_file,
+                language: wr.language,
                 tags,
                 parents,
                 debug: None,
+                hanja: None,
+                grammar: vec![],
                 resolved_status: wr.status.map(|s| (s, s)),
             })
         }
@@ -251,6 +305,17 @@ impl Dictionary {
     }
 
     pub async fn word_exists(&self, word: &str) -> Result<bool> {
+        let exists = self.lookup_word_exists(word).await?;
+        let _ = CHECKED_WORDS.try_with(|checked| checked.borrow_mut().insert(word.to_string(), exists));
+        Ok(exists)
+    }
+
+    async fn lookup_word_exists(&self, word: &str) -> Result<bool> {
+        if self.language.is_some() {
+            // The text index is kept for all languages, so only a complete
+            // index entry tells whether a word exists in this language.
+            return Ok(!self.find_words_by_text(word).await?.is_empty());
+        }
         if let Some(exists) = self.cache.read().await.exists_by_text(word) {
             return Ok(exists);
         }
@@ -262,8 +327,66 @@ impl Dictionary {
         Ok(count > 0)
     }
 
+    /// Returns which of the given texts are the text of a word, with a single
+    /// query (or none, if all words are cached). Unlike `word_exists`, the
+    /// answers aren't recorded for the segmentation cache.
+    pub async fn existing_texts(&self, texts: &[&str]) -> Result<HashSet<String>> {
+        {
+            let cache = self.cache.read().await;
+            if cache.text_index_has_all_words {
+                return Ok(texts.iter()
+                    .filter(|text| cache.text_index.get(**text).is_some_and(|index| index.word_ids.iter()
+                        .flat_map(|id| cache.words.get(id))
+                        .any(|word| self.in_language(word))))
+                    .map(|text| text.to_string())
+                    .collect());
+            }
+        }
+        let existing: Vec<(String,)> = sqlx::query_as("
+            SELECT DISTINCT text
+            FROM word
+            WHERE text IN (SELECT value FROM json_each(?))
+              AND (? IS NULL OR language IS NULL OR language = ?)
+            ")
+            .bind(serde_json::to_string(texts)?)
+            .bind(&self.language)
+            .bind(&self.language)
+            .fetch_all(&self.db)
+            .await?;
+        Ok(existing.into_iter().map(|(text,)| text).collect())
+    }
+
+    /// Returns the distinct texts of words containing spaces (e.g., idioms and
+    /// set phrases).
+    pub async fn find_phrases(&self) -> Result<Vec<String>> {
+        {
+            let cache = self.cache.read().await;
+            if cache.text_index_has_all_words {
+                return Ok(cache.text_index.iter()
+                    .filter(|(text, index)| text.contains(' ') && index.word_ids.iter()
+                        .flat_map(|id| cache.words.get(id))
+                        .any(|word| self.in_language(word)))
+                    .map(|(text, _)| text.clone())
+                    .sorted()
+                    .collect());
+            }
+        }
+        let phrases: Vec<(String,)> = sqlx::query_as("
+            SELECT DISTINCT text
+            FROM word
+            WHERE text LIKE '% %' AND (? IS NULL OR language IS NULL OR language = ?)
+            ORDER BY text
+            ")
+            .bind(&self.language)
+            .bind(&self.language)
+            .fetch_all(&self.db)
+            .await?;
+        Ok(phrases.into_iter().map(|(text,)| text).collect())
+    }
+
     pub async fn find_words_by_text(&self, text: &str) -> Result<Vec<Word>> {
-        if let Some(words) = self.cache.read().await.find_all_words_by_text(text) {
+        if let Some(mut words) = self.cache.read().await.find_all_words_by_text(text) {
+            words.retain(|word| self.in_language(word));
             return Ok(words);
         }
         let mut txn = self.db.begin().await?;
@@ -305,15 +428,19 @@ impl Dictionary {
                 pronunciation: wr.pronunciation,
                 translation: wr.translation,
                 image_file: wr.image_file,
+                language: wr.language,
                 tags,
                 parents,
                 debug: None,
+                hanja: None,
+                grammar: vec![],
                 resolved_status: wr.status.map(|s| (s, s)),
             })
         }
         let mut cache = self.cache.write().await;
         words.iter().for_each(|word| cache.insert_word(word));
         cache.set_text_index_complete(text);
+        words.retain(|word| self.in_language(word));
         Ok(words)
     }
 
@@ -322,44 +449,9 @@ impl Dictionary {
             return Ok(Some(word));
         }
         let mut txn = self.db.begin().await?;
-        let word_rec: Option<DbWord> = sqlx::query_as("SELECT * FROM word WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&mut *txn)
-            .await?;
-        let Some(word_rec) = word_rec else {
+        let Some(word) = load_word(&mut txn, id).await? else {
             return Ok(None);
         };
-        let tags: Vec<(String,)> = sqlx::query_as("
-            SELECT tag
-            FROM word_tag INNER JOIN word ON word_id = id
-            WHERE id = ?
-            ")
-            .bind(id)
-            .fetch_all(&mut *txn)
-            .await?;
-        let tags = tags.into_iter().map(|(s,)| s).collect();
-        let parents: Vec<(String,)> = sqlx::query_as("
-            SELECT parent_word_text
-            FROM word_parent INNER JOIN word ON child_word_id = id
-            WHERE child_word_id = ?
-            ")
-            .bind(id)
-            .fetch_all(&mut *txn)
-            .await?;
-        let parents = parents.into_iter().map(|(s,)| s).collect();
-        let word = Word {
-            id: Some(word_rec.id),
-            text: word_rec.text,
-            status: word_rec.status,
-            inherit: word_rec.status.is_none(),
-            pronunciation: word_rec.pronunciation,
-            translation: word_rec.translation,
-            image_file: word_rec.image_file,
-            tags,
-            parents,
-            debug: None,
-            resolved_status: word_rec.status.map(|s| (s, s)),
-        };
         self.cache.write().await.insert_word(&word);
         Ok(Some(word))
     }
@@ -438,65 +530,20 @@ impl Dictionary {
     pub async fn delete_word(&self, id: i64) -> Result<()> {
         self.cache.write().await.invalidate_by_id(id);
         let mut txn = self.db.begin().await?;
-
-        let text: Option<(String,)> = sqlx::query_as("SELECT text FROM word WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&mut *txn)
-            .await?;
-        let Some(text) = text.map(|(s,)| s) else {
-            return not_found();
-        };
-        self.cache.write().await.invalidate_text_index_flags(&text);
-
-        let (siblings,): (i32,) = sqlx::query_as("
-            SELECT COUNT(*)
-            FROM word a INNER JOIN word b ON a.text = b.text
-            WHERE a.id = ?
-            ")
-            .bind(id)
-            .fetch_one(&mut *txn)
-            .await?;
-        if siblings == 1 {
-            let (children,): (i32,) = sqlx::query_as("
-                SELECT COUNT(*)
-                FROM word_parent wp INNER JOIN word w ON wp.parent_word_text = w.text
-                WHERE w.id = ?
-                ")
-                .bind(id)
-                .fetch_one(&mut *txn)
-                .await?;
-            check(children == 0, "word has children")?;
-        }
-
-        let (image_file,): (Option<String>,) = must(sqlx::query_as("SELECT image_file FROM word WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&mut *txn)
-            .await?)?;
-
-        sqlx::query("DELETE FROM word_parent WHERE child_word_id = ?")
-            .bind(id)
-            .execute(&mut *txn)
-            .await?;
-        sqlx::query("DELETE FROM word_tag WHERE word_id = ?")
-            .bind(id)
-            .execute(&mut *txn)
-            .await?;
-        sqlx::query("DELETE FROM word WHERE id = ?")
-            .bind(id)
-            .execute(&mut *txn)
-            .await?;
-
+        let (text, image_file) = remove_word(&mut txn, id).await?;
         txn.commit().await?;
         self.cache.write().await.invalidate_by_id(id);
         self.cache.write().await.invalidate_text_index_flags(&text);
+        self.remove_image_file(image_file).await
+    }
 
+    async fn remove_image_file(&self, image_file: Option<String>) -> Result<()> {
         if let Some(image_file) = image_file {
             let image_path = self.word_images_path.join(image_file);
             if tokio::fs::try_exists(&image_path).await? {
                 tokio::fs::remove_file(image_path).await?;
             }
         }
-
         Ok(())
     }
 
@@ -512,45 +559,7 @@ impl Dictionary {
     async fn insert_word(&self, word: &Word) -> Result<i64> {
         self.cache.write().await.invalidate_text_index_complete(&word.text);
         let mut txn = self.db.begin().await?;
-
-        let result = sqlx::query("
-            INSERT INTO word (text, pronunciation, translation, status)
-            VALUES (?, ?, ?, ?)
-            ")
-            .bind(&word.text)
-            .bind(word.pronunciation.as_ref().filter(|s| !s.is_empty()))
-            .bind(&word.translation)
-            .bind(word.status)
-            .execute(&mut *txn)
-            .await?;
-        let id = result.last_insert_rowid();
-
-        for tag in word.tags.iter() {
-            sqlx::query("INSERT INTO word_tag (word_id, tag) VALUES (?, ?)")
-                .bind(id)
-                .bind(tag)
-                .execute(&mut *txn)
-                .await?;
-            }
-
-        for parent in word.parents.iter() {
-            sqlx::query("INSERT INTO word_parent (child_word_id, parent_word_text) VALUES (?, ?)")
-                .bind(id)
-                .bind(parent)
-                .execute(&mut *txn)
-                .await?;
-            }
-
-        let (count,): (i32,) = sqlx::query_as("
-            SELECT COUNT(*)
-            FROM word_parent wp LEFT JOIN word w ON wp.parent_word_text = w.text
-            WHERE wp.child_word_id = ? AND w.id IS NULL
-            ")
-            .bind(id)
-            .fetch_one(&mut *txn)
-            .await?;
-        check(count == 0, "missing parents")?;
-
+        let id = create_word(&mut txn, word).await?;
         txn.commit().await?;
         self.cache.write().await.invalidate_text_index_complete(&word.text);
         self.cache.write().await.set_word_exists(&word.text, true);
@@ -560,57 +569,222 @@ impl Dictionary {
     async fn update_word(&self, id: i64, word: &Word) -> Result<()> {
         self.cache.write().await.invalidate_by_id(id);
         let mut txn = self.db.begin().await?;
+        save_word(&mut txn, id, word).await?;
+        txn.commit().await?;
+        self.cache.write().await.invalidate_by_id(id);
+        self.cache.write().await.set_word_exists(&word.text, true);
+        Ok(())
+    }
+}
 
-        let result = sqlx::query("
-            UPDATE word
-            SET text = ?, status = ?, pronunciation = ?, translation = ?
-            WHERE id = ?
-            ")
-            .bind(&word.text)
-            .bind(word.status)
-            .bind(word.pronunciation.as_ref().filter(|s| !s.is_empty()))
-            .bind(&word.translation)
+/// Loads a word from the database, bypassing the cache.
+async fn load_word(conn: &mut SqliteConnection, id: i64) -> Result<Option<Word>> {
+    let word_rec: Option<DbWord> = sqlx::query_as("SELECT * FROM word WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let Some(word_rec) = word_rec else {
+        return Ok(None);
+    };
+    let tags: Vec<(String,)> = sqlx::query_as("
+        SELECT tag
+        FROM word_tag INNER JOIN word ON word_id = id
+        WHERE id = ?
+        ")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let tags = tags.into_iter().map(|(s,)| s).collect();
+    let parents: Vec<(String,)> = sqlx::query_as("
+        SELECT parent_word_text
+        FROM word_parent INNER JOIN word ON child_word_id = id
+        WHERE child_word_id = ?
+        ")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let parents = parents.into_iter().map(|(s,)| s).collect();
+    let word = Word {
+        id: Some(word_rec.id),
+        text: word_rec.text,
+        status: word_rec.status,
+        inherit: word_rec.status.is_none(),
+        pronunciation: word_rec.pronunciation,
+        translation: word_rec.translation,
+        image_file: word_rec.image_file,
+        language: word_rec.language,
+        tags,
+        parents,
+        debug: None,
+        hanja: None,
+        grammar: vec![],
+        resolved_status: word_rec.status.map(|s| (s, s)),
+    };
+    Ok(Some(word))
+}
+
+/// Inserts a new word, recording its history. Returns the ID of the word.
+async fn create_word(conn: &mut SqliteConnection, word: &Word) -> Result<i64> {
+    // The ID is only set when restoring a deleted word.
+    let result = sqlx::query("
+        INSERT INTO word (id, text, pronunciation, translation, status, language, added)
+        VALUES (?, ?, ?, ?, ?, ?, current_timestamp)
+        ")
+        .bind(word.id)
+        .bind(&word.text)
+        .bind(word.pronunciation.as_ref().filter(|s| !s.is_empty()))
+        .bind(&word.translation)
+        .bind(word.status)
+        .bind(word.language.as_ref().filter(|s| !s.is_empty()))
+        .execute(&mut *conn)
+        .await?;
+    let id = result.last_insert_rowid();
+    history::record_status(conn, id, None, word.status).await?;
+    history::record_tags(conn, id, &[], &word.tags).await?;
+
+    for tag in word.tags.iter() {
+        sqlx::query("INSERT INTO word_tag (word_id, tag) VALUES (?, ?)")
             .bind(id)
-            .execute(&mut *txn)
+            .bind(tag)
+            .execute(&mut *conn)
             .await?;
-        if result.rows_affected() != 1 {
-            not_found()?;
         }
 
-        sqlx::query("DELETE FROM word_tag WHERE word_id = ?")
+    for parent in word.parents.iter() {
+        sqlx::query("INSERT INTO word_parent (child_word_id, parent_word_text) VALUES (?, ?)")
             .bind(id)
-            .execute(&mut *txn)
+            .bind(parent)
+            .execute(&mut *conn)
             .await?;
-        for tag in word.tags.iter() {
-            sqlx::query("INSERT INTO word_tag (word_id, tag) VALUES (?, ?)")
-                .bind(id)
-                .bind(tag)
-                .execute(&mut *txn)
-                .await?;
         }
 
-        sqlx::query("DELETE FROM word_parent WHERE child_word_id = ?")
+    let (count,): (i32,) = sqlx::query_as("
+        SELECT COUNT(*)
+        FROM word_parent wp LEFT JOIN word w ON wp.parent_word_text = w.text
+        WHERE wp.child_word_id = ? AND w.id IS NULL
+        ")
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+    check(count == 0, "missing parents")?;
+    revision::record(conn, id, &word.into(), false).await?;
+    Ok(id)
+}
+
+/// Overwrites the stored fields of an existing word, recording its history.
+async fn save_word(conn: &mut SqliteConnection, id: i64, word: &Word) -> Result<()> {
+    let (old_status,): (Option<WordStatus>,) = must(sqlx::query_as("SELECT status FROM word WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?)?;
+    let old_tags: Vec<(String,)> = sqlx::query_as("SELECT tag FROM word_tag WHERE word_id = ?")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let old_tags = old_tags.into_iter().map(|(s,)| s).collect_vec();
+    history::record_status(conn, id, old_status, word.status).await?;
+    history::record_tags(conn, id, &old_tags, &word.tags).await?;
+
+    let result = sqlx::query("
+        UPDATE word
+        SET text = ?, status = ?, pronunciation = ?, translation = ?, language = COALESCE(?, language)
+        WHERE id = ?
+        ")
+        .bind(&word.text)
+        .bind(word.status)
+        .bind(word.pronunciation.as_ref().filter(|s| !s.is_empty()))
+        .bind(&word.translation)
+        .bind(word.language.as_ref().filter(|s| !s.is_empty()))
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    if result.rows_affected() != 1 {
+        not_found()?;
+    }
+
+    sqlx::query("DELETE FROM word_tag WHERE word_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    for tag in word.tags.iter() {
+        sqlx::query("INSERT INTO word_tag (word_id, tag) VALUES (?, ?)")
             .bind(id)
-            .execute(&mut *txn)
+            .bind(tag)
+            .execute(&mut *conn)
             .await?;
-        for parent in word.parents.iter() {
-            let (count,): (i32,) = sqlx::query_as("SELECT COUNT(*) FROM word WHERE text = ?")
-                           .bind(parent)
-                           .fetch_one(&mut *txn)
-                           .await?;
-            check(count != 0, "missing parent")?;
-            sqlx::query("INSERT INTO word_parent (child_word_id, parent_word_text) VALUES (?, ?)")
-                .bind(id)
-                .bind(parent)
-                .execute(&mut *txn)
-                .await?;
-        }
+    }
 
-        txn.commit().await?;
-        self.cache.write().await.invalidate_by_id(id);
-        self.cache.write().await.set_word_exists(&word.text, true);
-        Ok(())
+    sqlx::query("DELETE FROM word_parent WHERE child_word_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    for parent in word.parents.iter() {
+        let (count,): (i32,) = sqlx::query_as("SELECT COUNT(*) FROM word WHERE text = ?")
+                       .bind(parent)
+                       .fetch_one(&mut *conn)
+                       .await?;
+        check(count != 0, "missing parent")?;
+        sqlx::query("INSERT INTO word_parent (child_word_id, parent_word_text) VALUES (?, ?)")
+            .bind(id)
+            .bind(parent)
+            .execute(&mut *conn)
+            .await?;
     }
+    revision::record(conn, id, &word.into(), false).await?;
+    Ok(())
+}
+
+/// Deletes a word that no other word depends on, with its tags, parents,
+/// review schedule and history, and records its last revision. Returns the
+/// text of the word and its image file, which is left for the caller to
+/// delete once the transaction is committed.
+async fn remove_word(conn: &mut SqliteConnection, id: i64) -> Result<(String, Option<String>)> {
+    let Some(old_word) = load_word(conn, id).await? else {
+        return not_found();
+    };
+
+    let (siblings,): (i32,) = sqlx::query_as("
+        SELECT COUNT(*)
+        FROM word a INNER JOIN word b ON a.text = b.text
+        WHERE a.id = ?
+        ")
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if siblings == 1 {
+        let (children,): (i32,) = sqlx::query_as("
+            SELECT COUNT(*)
+            FROM word_parent wp INNER JOIN word w ON wp.parent_word_text = w.text
+            WHERE w.id = ?
+            ")
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+        check(children == 0, "word has children")?;
+    }
+
+    sqlx::query("DELETE FROM word_parent WHERE child_word_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM word_tag WHERE word_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM review WHERE word_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM word_event WHERE word_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM word WHERE id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    revision::record(conn, id, &(&old_word).into(), true).await?;
+    Ok((old_word.text, old_word.image_file))
 }
 
 fn fold_status_range_possibilities(x: (WordStatus, WordStatus), y: (WordStatus, WordStatus)) -> Result<(WordStatus, WordStatus)> {
@@ -691,12 +865,14 @@ impl Dictionary {
                         ELSE SUBSTR(translation, 1, INSTR(translation, CHAR(10) || CHAR(10)) - 1)
                     END, '; ') AS translation
             FROM word
-            WHERE text LIKE ?
+            WHERE text LIKE ? AND (? IS NULL OR language IS NULL OR language = ?)
             GROUP BY 1
             ORDER BY LENGTH(text) ASC, text ASC
             LIMIT 20
         ")
             .bind(format!("{}%", q))
+            .bind(&self.language)
+            .bind(&self.language)
             .fetch_all(&self.db)
             .await?;
         results.sort_by_key(|ws| ws.value.to_string());
@@ -719,7 +895,9 @@ pub struct WordRow {
 }
 
 impl Dictionary {
-    pub async fn fetch_dt(&self, req: dt::Request) -> Result<dt::Response<WordRow>> {
+    /// Builds the selected columns, the `WHERE` clause (if any rows are filtered
+    /// out) and its bound values of a DataTables query on the `word` table.
+    fn dt_query(req: &dt::Request) -> Result<(Vec<&str>, Option<String>, Vec<String>)> {
         let mut columns: Vec<&str> = vec![];
         let mut filters: Vec<&str> = vec![];
         let mut binds = vec![];
@@ -808,8 +986,15 @@ impl Dictionary {
             }
         }
 
+        let filter = (!filters.is_empty()).then(|| filters.join(" AND "));
+        Ok((columns, filter, binds))
+    }
+
+    pub async fn fetch_dt(&self, req: dt::Request) -> Result<dt::Response<WordRow>> {
+        let (columns, filter, binds) = Self::dt_query(&req)?;
+
         let mut orders = vec![];
-        for order in req.order.unwrap_or_default() {
+        for order in req.order.iter().flatten() {
             orders.push(match order.dir {
                 dt::Dir::Asc => format!("{} ASC", order.column + 2),
                 dt::Dir::Desc => format!("{} DESC", order.column + 2),
@@ -819,9 +1004,9 @@ impl Dictionary {
         let mut clauses: Vec<String> = vec!["SELECT id,".to_string()];
         clauses.push(columns.join(", "));
         clauses.push("FROM word".to_string());
-        if !filters.is_empty() {
+        if let Some(ref filter) = filter {
             clauses.push("WHERE".to_string());
-            clauses.push(filters.join(" AND "));
+            clauses.push(filter.clone());
         }
         if !orders.is_empty() {
             clauses.push("ORDER BY".to_string());
@@ -848,12 +1033,8 @@ impl Dictionary {
         let (records_total,): (u64,) = sqlx::query_as("SELECT COUNT(*) FROM word").fetch_one(&self.db).await?;
         let records_total = records_total as usize;
 
-        let records_filtered = if filters.is_empty() {
-            records_total
-        } else {
-            let sql = format!(
-                "SELECT COUNT(*) FROM word WHERE {}",
-                filters.join(" AND "));
+        let records_filtered = if let Some(filter) = filter {
+            let sql = format!("SELECT COUNT(*) FROM word WHERE {filter}");
             // dbg!(&sql);
             let mut query = sqlx::query_as(&sql);
             for bind in binds {
@@ -861,6 +1042,8 @@ impl Dictionary {
             }
             let (count,): (u64,) = query.fetch_one(&self.db).await?;
             count as usize
+        } else {
+            records_total
         };
 
         Ok(dt::Response{
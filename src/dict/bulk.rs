@@ -0,0 +1,207 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::Deserialize;
+
+use crate::{bad_req, check, dt, Result};
+
+use super::{create_word, load_word, remove_word, save_word, Dictionary, Word, WordStatus, EMPTY_WORD};
+
+/// A change to apply to each of a set of words.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkOperation {
+    /// Sets the status of the words. A missing status makes them inherit it
+    /// from their parents.
+    SetStatus { status: Option<WordStatus> },
+    AddTag { tag: String },
+    RemoveTag { tag: String },
+    AddParent { parent: String },
+    Delete,
+}
+
+/// The words to apply a bulk operation to: either the given IDs, or all words
+/// matching the filters of a words table query (ignoring its paging).
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkSelection {
+    Ids(Vec<i64>),
+    Filter(dt::Request),
+}
+
+impl Dictionary {
+    /// Applies the operation to all selected words in a single transaction,
+    /// so that either all of them are changed or none is. Returns the IDs of
+    /// the words that were changed; words the operation wouldn't change are
+    /// left alone.
+    pub async fn apply_bulk(&self, selection: BulkSelection, op: &BulkOperation) -> Result<Vec<i64>> {
+        match op {
+            BulkOperation::AddTag { tag } | BulkOperation::RemoveTag { tag } => check(!tag.is_empty(), "empty tag")?,
+            BulkOperation::AddParent { parent } => check(!parent.is_empty(), "empty parent")?,
+            _ => {},
+        }
+
+        let mut txn = self.db.begin().await?;
+        let ids = match selection {
+            BulkSelection::Ids(ids) => ids,
+            BulkSelection::Filter(req) => {
+                let (_, filter, binds) = Self::dt_query(&req)?;
+                let Some(filter) = filter else {
+                    return bad_req("filter matches all words");
+                };
+                let sql = format!("SELECT id FROM word WHERE {filter} ORDER BY id");
+                let mut query = sqlx::query_as(&sql);
+                for bind in binds {
+                    query = query.bind(bind);
+                }
+                let ids: Vec<(i64,)> = query.fetch_all(&mut *txn).await?;
+                ids.into_iter().map(|(id,)| id).collect()
+            },
+        };
+
+        let mut changed = vec![];
+        let mut deleted = vec![];
+        for id in ids {
+            if changed.contains(&id) {
+                continue;
+            }
+            let Some(mut word) = load_word(&mut txn, id).await? else {
+                return bad_req(format!("word not found: {id}").as_str());
+            };
+            match op {
+                BulkOperation::SetStatus { status } => {
+                    if word.status == *status {
+                        continue;
+                    }
+                    word.status = *status;
+                },
+                BulkOperation::AddTag { tag } => {
+                    if word.tags.contains(tag) {
+                        continue;
+                    }
+                    word.tags.push(tag.clone());
+                },
+                BulkOperation::RemoveTag { tag } => {
+                    if !word.tags.contains(tag) {
+                        continue;
+                    }
+                    word.tags.retain(|t| t != tag);
+                },
+                BulkOperation::AddParent { parent } => {
+                    if word.parents.contains(parent) {
+                        continue;
+                    }
+                    check(word.text != *parent, "word can't be its own parent")?;
+                    word.parents.push(parent.clone());
+                },
+                BulkOperation::Delete => {
+                    deleted.push(remove_word(&mut txn, id).await?);
+                    changed.push(id);
+                    continue;
+                },
+            }
+            save_word(&mut txn, id, &word).await?;
+            changed.push(id);
+        }
+        txn.commit().await?;
+
+        {
+            let mut cache = self.cache.write().await;
+            for id in changed.iter() {
+                cache.invalidate_by_id(*id);
+            }
+            for (text, _) in deleted.iter() {
+                cache.invalidate_text_index_flags(text);
+            }
+        }
+        for (_, image_file) in deleted {
+            self.remove_image_file(image_file).await?;
+        }
+        Ok(changed)
+    }
+
+    /// Returns the unknown root words of the given words, i.e., the words
+    /// whose status is `Unknown` and doesn't come from a parent, with the
+    /// words that aren't in the dictionary yet (having no ID). Words that
+    /// inherit their status, and words produced by the parser, are followed
+    /// through to their parents. Words with any other status are skipped.
+    pub async fn unknown_roots(&self, words: impl IntoIterator<Item = Word>) -> Result<Vec<Word>> {
+        let mut roots = vec![];
+        let mut seen_ids = HashSet::new();
+        let mut seen_texts = HashSet::new();
+        let mut q = VecDeque::from_iter(words);
+        while let Some(word) = q.pop_front() {
+            let follow_parents = match (word.id, word.status) {
+                (Some(id), _) if !seen_ids.insert(id) => continue,
+                (Some(_), Some(WordStatus::Unknown)) => false,
+                (Some(_), None) => true,
+                (Some(_), Some(_)) => continue,
+                (None, _) => !word.parents.is_empty(),
+            };
+            if !follow_parents {
+                roots.push(word);
+                continue;
+            }
+            for parent in word.parents.iter() {
+                if !seen_texts.insert(parent.clone()) {
+                    continue;
+                }
+                let parent_words = self.find_words_by_text(parent).await?;
+                if parent_words.is_empty() {
+                    roots.push(Word { text: parent.clone(), ..EMPTY_WORD });
+                }
+                q.extend(parent_words);
+            }
+        }
+
+        // A word the parser found might already be in the dictionary.
+        let mut new_texts = HashSet::new();
+        let mut unknown = vec![];
+        for word in roots {
+            if word.id.is_none() && (!new_texts.insert(word.text.clone()) || self.word_exists(&word.text).await?) {
+                continue;
+            }
+            unknown.push(word);
+        }
+        Ok(unknown)
+    }
+
+    /// Sets the status of the given words in a single transaction, adding the
+    /// words that aren't in the dictionary yet (in the given language, with
+    /// only their text).
+    /// Returns the IDs of the words.
+    pub async fn set_words_status(&self, words: Vec<Word>, status: WordStatus, language: Option<&str>) -> Result<Vec<i64>> {
+        let mut txn = self.db.begin().await?;
+        let mut ids = vec![];
+        let mut new_texts = vec![];
+        for word in words {
+            if let Some(id) = word.id {
+                let Some(mut word) = load_word(&mut txn, id).await? else {
+                    return bad_req(format!("word not found: {id}").as_str());
+                };
+                word.status = Some(status);
+                save_word(&mut txn, id, &word).await?;
+                ids.push(id);
+            } else {
+                let word = Word {
+                    text: word.text,
+                    status: Some(status),
+                    language: language.map(|s| s.to_string()),
+                    ..EMPTY_WORD
+                };
+                ids.push(create_word(&mut txn, &word).await?);
+                new_texts.push(word.text);
+            }
+        }
+        txn.commit().await?;
+
+        let mut cache = self.cache.write().await;
+        for id in ids.iter() {
+            cache.invalidate_by_id(*id);
+        }
+        for text in new_texts.iter() {
+            cache.invalidate_text_index_complete(text);
+            cache.set_word_exists(text, true);
+        }
+        Ok(ids)
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use sqlx::SqliteConnection;
+
+use crate::Result;
+
+use super::{Dictionary, WordStatus};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum WordEventKind {
+    /// The status of the word changed from `old_status` to `new_status`. A
+    /// missing status means the word inherits it from its parents.
+    Status,
+    TagAdded,
+    TagRemoved,
+}
+
+/// A change to a word, as recorded in the `word_event` table.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct WordEvent {
+    pub time: String,
+    pub kind: WordEventKind,
+    pub old_status: Option<WordStatus>,
+    pub new_status: Option<WordStatus>,
+    pub tag: Option<String>,
+}
+
+/// The number of words at each status at the end of a day.
+#[derive(Clone, Debug, Serialize)]
+pub struct TimelineDay {
+    pub day: String,
+    pub counts: BTreeMap<WordStatus, usize>,
+}
+
+/// Records a change to the status of a word, if it changed.
+pub(super) async fn record_status(conn: &mut SqliteConnection, word_id: i64, old: Option<WordStatus>, new: Option<WordStatus>) -> Result<()> {
+    if old == new {
+        return Ok(());
+    }
+    sqlx::query("INSERT INTO word_event (word_id, kind, old_status, new_status) VALUES (?, ?, ?, ?)")
+        .bind(word_id)
+        .bind(WordEventKind::Status)
+        .bind(old)
+        .bind(new)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Records the tags added to and removed from a word.
+pub(super) async fn record_tags(conn: &mut SqliteConnection, word_id: i64, old: &[String], new: &[String]) -> Result<()> {
+    let added = new.iter().filter(|t| !old.contains(t)).map(|t| (WordEventKind::TagAdded, t));
+    let removed = old.iter().filter(|t| !new.contains(t)).map(|t| (WordEventKind::TagRemoved, t));
+    for (kind, tag) in added.chain(removed) {
+        sqlx::query("INSERT INTO word_event (word_id, kind, tag) VALUES (?, ?, ?)")
+            .bind(word_id)
+            .bind(kind)
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+impl Dictionary {
+    /// Returns the recorded changes to a word, oldest first.
+    pub async fn word_history(&self, word_id: i64) -> Result<Vec<WordEvent>> {
+        let events = sqlx::query_as("
+            SELECT time, kind, old_status, new_status, tag
+            FROM word_event
+            WHERE word_id = ?
+            ORDER BY time, id
+            ")
+            .bind(word_id)
+            .fetch_all(&self.db)
+            .await?;
+        Ok(events)
+    }
+
+    /// Returns the number of words at each status at the end of each day on
+    /// which words were added or their status changed. Words are counted from
+    /// their first recorded change. Words added before their history was
+    /// recorded are counted at their current status from the day they were
+    /// added (or from the start, if that isn't known: `added` defaults to the
+    /// literal 'now'). If `language` is given, words without a language are
+    /// included.
+    pub async fn status_timeline(&self, language: Option<&str>) -> Result<Vec<TimelineDay>> {
+        let mut txn = self.db.begin().await?;
+        let words: Vec<(i64, Option<String>, Option<WordStatus>)> = sqlx::query_as("
+            SELECT id, date(NULLIF(added, 'now')), status
+            FROM word
+            WHERE ? IS NULL OR language IS NULL OR language = ?
+            ")
+            .bind(language)
+            .bind(language)
+            .fetch_all(&mut *txn)
+            .await?;
+        let events: Vec<(i64, String, Option<WordStatus>, Option<WordStatus>)> = sqlx::query_as("
+            SELECT e.word_id, date(e.time), e.old_status, e.new_status
+            FROM word_event e INNER JOIN word w ON e.word_id = w.id
+            WHERE e.kind = 'status'
+              AND (? IS NULL OR w.language IS NULL OR w.language = ?)
+            ORDER BY e.time, e.id
+            ")
+            .bind(language)
+            .bind(language)
+            .fetch_all(&mut *txn)
+            .await?;
+
+        // Each word starts out with the status it had before its first
+        // recorded change, on the day of that change.
+        let mut initial: HashMap<i64, (String, Option<WordStatus>)> = HashMap::new();
+        for (word_id, day, old, _) in events.iter() {
+            initial.entry(*word_id).or_insert((day.clone(), *old));
+        }
+        let mut changes: Vec<(String, i64, Option<WordStatus>)> = words.into_iter()
+            .map(|(id, day, status)| match initial.remove(&id) {
+                Some((day, old)) => (day, id, old),
+                None => (day.unwrap_or_default(), id, status),
+            })
+            .collect();
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut timeline = vec![];
+        let mut stati: HashMap<i64, Option<WordStatus>> = HashMap::new();
+        let mut changes = changes.into_iter().peekable();
+        let mut events = events.into_iter()
+            .map(|(word_id, day, _, new)| (day, word_id, new))
+            .peekable();
+        loop {
+            let day = match (changes.peek(), events.peek()) {
+                (Some(c), Some(e)) => c.0.clone().min(e.0.clone()),
+                (Some(c), None) => c.0.clone(),
+                (None, Some(e)) => e.0.clone(),
+                (None, None) => break,
+            };
+            while let Some((_, id, status)) = changes.next_if(|c| c.0 == day) {
+                stati.insert(id, status);
+            }
+            while let Some((_, id, status)) = events.next_if(|e| e.0 == day) {
+                stati.insert(id, status);
+            }
+            // Words added on an unknown day are counted from the first day.
+            if day.is_empty() {
+                continue;
+            }
+            let mut counts = BTreeMap::new();
+            for status in stati.values().flatten() {
+                *counts.entry(*status).or_default() += 1;
+            }
+            timeline.push(TimelineDay { day, counts });
+        }
+        Ok(timeline)
+    }
+}
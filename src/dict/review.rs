@@ -0,0 +1,232 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{bad_req, must, Result};
+
+use super::{load_word, save_word, Dictionary, Word, WordStatus};
+
+/// How soon a word is shown again after it was forgotten.
+const RELEARN_DELAY_MINUTES: i64 = 10;
+
+/// The ease of a word that hasn't been reviewed yet, and the lowest it may go.
+const INITIAL_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+
+/// How well a word was remembered when it was reviewed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewGrade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl ReviewGrade {
+    /// The SM-2 quality of the response, from 0 to 5.
+    fn quality(self) -> f64 {
+        match self {
+            Self::Again => 1.0,
+            Self::Hard => 3.0,
+            Self::Good => 4.0,
+            Self::Easy => 5.0,
+        }
+    }
+}
+
+/// The SM-2 scheduling state of a word.
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct ReviewState {
+    pub word_id: i64,
+    pub due: DateTime<Utc>,
+    pub interval_days: f64,
+    pub ease: f64,
+    pub repetitions: i64,
+    pub lapses: i64,
+    pub last_review: Option<DateTime<Utc>>,
+}
+
+impl ReviewState {
+    /// Returns the schedule of a word that hasn't been reviewed yet. It starts
+    /// as if the word had been reviewed until it reached its current status,
+    /// so that reviewing a word that is already well on its way isn't taken
+    /// as a first review (which would lower its status).
+    pub fn new(word_id: i64, status: WordStatus, now: DateTime<Utc>) -> Self {
+        let (repetitions, interval_days) = match status {
+            WordStatus::Level2 => (1, 1.0),
+            WordStatus::Level3 => (2, 6.0),
+            WordStatus::Level4 => (3, 14.0),
+            WordStatus::Level5 => (4, 45.0),
+            _ => (0, 0.0),
+        };
+        Self {
+            word_id,
+            due: now,
+            interval_days,
+            ease: INITIAL_EASE,
+            repetitions,
+            lapses: 0,
+            last_review: None,
+        }
+    }
+
+    /// Schedules the next review of the word after it was graded at `now`.
+    pub fn grade(&mut self, grade: ReviewGrade, now: DateTime<Utc>) {
+        let q = grade.quality();
+        self.ease = (self.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASE);
+        self.last_review = Some(now);
+        if grade == ReviewGrade::Again {
+            self.repetitions = 0;
+            self.lapses += 1;
+            self.interval_days = 0.0;
+            self.due = now + Duration::minutes(RELEARN_DELAY_MINUTES);
+            return;
+        }
+        self.repetitions += 1;
+        self.interval_days = match (self.repetitions, grade) {
+            (1, _) => 1.0,
+            (2, _) => 6.0,
+            (_, ReviewGrade::Hard) => self.interval_days * 1.2,
+            (_, ReviewGrade::Easy) => self.interval_days * self.ease * 1.3,
+            _ => self.interval_days * self.ease,
+        }.round();
+        self.due = now + Duration::days(self.interval_days as i64);
+    }
+
+    /// Returns the word status corresponding to how long the word is
+    /// remembered for. A word that is remembered for three months is well known,
+    /// and won't be reviewed again.
+    pub fn status(&self) -> WordStatus {
+        if self.repetitions == 0 {
+            return WordStatus::New;
+        }
+        match self.interval_days {
+            d if d < 3.0 => WordStatus::Level2,
+            d if d < 7.0 => WordStatus::Level3,
+            d if d < 21.0 => WordStatus::Level4,
+            d if d < 90.0 => WordStatus::Level5,
+            _ => WordStatus::WellKnown,
+        }
+    }
+}
+
+/// A word to review, with its schedule if it has been reviewed before.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReviewCard {
+    pub word: Word,
+    pub due: Option<String>,
+    pub interval_days: f64,
+    pub repetitions: i64,
+    pub lapses: i64,
+}
+
+impl Dictionary {
+    async fn review_card(&self, word_id: i64, state: Option<&ReviewState>) -> Result<ReviewCard> {
+        let word = must(self.find_word_by_id(word_id).await?)?;
+        Ok(ReviewCard {
+            word,
+            due: state.map(|s| s.due.to_rfc3339()),
+            interval_days: state.map(|s| s.interval_days).unwrap_or_default(),
+            repetitions: state.map(|s| s.repetitions).unwrap_or_default(),
+            lapses: state.map(|s| s.lapses).unwrap_or_default(),
+        })
+    }
+
+    /// Returns up to `limit` words that are due for review, earliest first,
+    /// followed by up to `new_limit` words that haven't been reviewed yet,
+    /// oldest first. Only words with a learning status (new to level 5) are
+    /// reviewed. If `language` is given, words without a language are included.
+    pub async fn due_reviews(&self, language: Option<&str>, limit: usize, new_limit: usize) -> Result<Vec<ReviewCard>> {
+        let now = Utc::now();
+        let due: Vec<ReviewState> = sqlx::query_as("
+            SELECT r.word_id, r.due, r.interval_days, r.ease, r.repetitions, r.lapses, r.last_review
+            FROM review r INNER JOIN word w ON r.word_id = w.id
+            WHERE w.status BETWEEN 1 AND 5
+              AND r.due <= ?
+              AND (? IS NULL OR w.language IS NULL OR w.language = ?)
+            ORDER BY r.due
+            LIMIT ?
+            ")
+            .bind(now)
+            .bind(language)
+            .bind(language)
+            .bind(limit as i64)
+            .fetch_all(&self.db)
+            .await?;
+        let new: Vec<(i64,)> = sqlx::query_as("
+            SELECT w.id
+            FROM word w LEFT JOIN review r ON r.word_id = w.id
+            WHERE r.word_id IS NULL
+              AND w.status BETWEEN 1 AND 5
+              AND (? IS NULL OR w.language IS NULL OR w.language = ?)
+            ORDER BY w.added, w.id
+            LIMIT ?
+            ")
+            .bind(language)
+            .bind(language)
+            .bind(new_limit as i64)
+            .fetch_all(&self.db)
+            .await?;
+
+        let mut cards = vec![];
+        for state in due.iter() {
+            cards.push(self.review_card(state.word_id, Some(state)).await?);
+        }
+        for (word_id,) in new {
+            cards.push(self.review_card(word_id, None).await?);
+        }
+        Ok(cards)
+    }
+
+    /// Records a review of the word, scheduling the next one and setting the
+    /// word's status to match. Returns the new schedule.
+    pub async fn review_word(&self, word_id: i64, grade: ReviewGrade) -> Result<ReviewState> {
+        let now = Utc::now();
+        let mut txn = self.db.begin().await?;
+
+        let mut word = must(load_word(&mut txn, word_id).await?)?;
+        let Some(status) = word.status else {
+            return bad_req("word inherits its status from its parents");
+        };
+
+        let state: Option<ReviewState> = sqlx::query_as("
+            SELECT word_id, due, interval_days, ease, repetitions, lapses, last_review
+            FROM review
+            WHERE word_id = ?
+            ")
+            .bind(word_id)
+            .fetch_optional(&mut *txn)
+            .await?;
+        let mut state = state.unwrap_or_else(|| ReviewState::new(word_id, status, now));
+        state.grade(grade, now);
+
+        sqlx::query("
+            INSERT INTO review (word_id, due, interval_days, ease, repetitions, lapses, last_review)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (word_id) DO UPDATE SET
+              due = excluded.due,
+              interval_days = excluded.interval_days,
+              ease = excluded.ease,
+              repetitions = excluded.repetitions,
+              lapses = excluded.lapses,
+              last_review = excluded.last_review
+            ")
+            .bind(word_id)
+            .bind(state.due)
+            .bind(state.interval_days)
+            .bind(state.ease)
+            .bind(state.repetitions)
+            .bind(state.lapses)
+            .bind(state.last_review)
+            .execute(&mut *txn)
+            .await?;
+        if word.status != Some(state.status()) {
+            word.status = Some(state.status());
+            save_word(&mut txn, word_id, &word).await?;
+        }
+
+        txn.commit().await?;
+        self.cache.write().await.invalidate_by_id(word_id);
+        Ok(state)
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+
+use crate::{check, must, Result};
+
+use super::{Dictionary, Word, WordStatus, EMPTY_WORD};
+
+/// The stored fields of a word, as saved in a revision.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordSnapshot {
+    pub text: String,
+    pub status: Option<WordStatus>,
+    pub pronunciation: Option<String>,
+    pub translation: String,
+    pub tags: Vec<String>,
+    pub parents: Vec<String>,
+    pub language: Option<String>,
+}
+
+impl From<&Word> for WordSnapshot {
+    fn from(word: &Word) -> Self {
+        Self {
+            text: word.text.clone(),
+            status: word.status,
+            pronunciation: word.pronunciation.clone().filter(|s| !s.is_empty()),
+            translation: word.translation.clone(),
+            tags: word.tags.clone(),
+            parents: word.parents.clone(),
+            language: word.language.clone().filter(|s| !s.is_empty()),
+        }
+    }
+}
+
+impl WordSnapshot {
+    fn to_word(&self, id: i64) -> Word {
+        Word {
+            id: Some(id),
+            text: self.text.clone(),
+            status: self.status,
+            inherit: self.status.is_none(),
+            pronunciation: self.pronunciation.clone(),
+            translation: self.translation.clone(),
+            tags: self.tags.clone(),
+            parents: self.parents.clone(),
+            language: self.language.clone(),
+            ..EMPTY_WORD
+        }
+    }
+}
+
+/// A saved version of a word. A revision is recorded each time a word is
+/// saved, with the word as saved, and when it is deleted, with the word as it
+/// was before.
+#[derive(Clone, Debug, Serialize)]
+pub struct WordRevision {
+    pub id: i64,
+    pub word_id: i64,
+    pub time: String,
+    pub deleted: bool,
+    pub word: WordSnapshot,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbRevision {
+    id: i64,
+    word_id: i64,
+    time: String,
+    deleted: bool,
+    snapshot: String,
+}
+
+impl TryFrom<DbRevision> for WordRevision {
+    type Error = crate::Error;
+
+    fn try_from(rec: DbRevision) -> Result<Self> {
+        Ok(Self {
+            id: rec.id,
+            word_id: rec.word_id,
+            time: rec.time,
+            deleted: rec.deleted,
+            word: serde_json::from_str(&rec.snapshot)?,
+        })
+    }
+}
+
+/// A field that differs between two versions of a word.
+#[derive(Clone, Debug, Serialize)]
+pub struct FieldDiff {
+    pub field: String,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+/// Returns the fields that differ between two versions of a word. A missing
+/// version (e.g., of a deleted word) has no fields set.
+pub fn diff(old: Option<&WordSnapshot>, new: Option<&WordSnapshot>) -> Result<Vec<FieldDiff>> {
+    let empty = WordSnapshot::default();
+    let old = serde_json::to_value(old.unwrap_or(&empty))?;
+    let new = serde_json::to_value(new.unwrap_or(&empty))?;
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        return Ok(vec![]);
+    };
+    let diffs = old.iter()
+        .filter(|(field, value)| new.get(*field) != Some(*value))
+        .map(|(field, value)| FieldDiff {
+            field: field.clone(),
+            old: value.clone(),
+            new: new.get(field).cloned().unwrap_or_default(),
+        })
+        .collect();
+    Ok(diffs)
+}
+
+/// Records a revision of the word.
+pub(super) async fn record(conn: &mut SqliteConnection, word_id: i64, word: &WordSnapshot, deleted: bool) -> Result<()> {
+    sqlx::query("INSERT INTO word_revision (word_id, deleted, snapshot) VALUES (?, ?, ?)")
+        .bind(word_id)
+        .bind(deleted)
+        .bind(serde_json::to_string(word)?)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+impl Dictionary {
+    /// Returns the revisions of a word, newest first.
+    pub async fn word_revisions(&self, word_id: i64) -> Result<Vec<WordRevision>> {
+        let recs: Vec<DbRevision> = sqlx::query_as("
+            SELECT id, word_id, time, deleted, snapshot
+            FROM word_revision
+            WHERE word_id = ?
+            ORDER BY id DESC
+            ")
+            .bind(word_id)
+            .fetch_all(&self.db)
+            .await?;
+        recs.into_iter().map(|rec| rec.try_into()).collect()
+    }
+
+    pub async fn find_word_revision(&self, word_id: i64, revision_id: i64) -> Result<WordRevision> {
+        let rec: DbRevision = must(sqlx::query_as("
+            SELECT id, word_id, time, deleted, snapshot
+            FROM word_revision
+            WHERE word_id = ? AND id = ?
+            ")
+            .bind(word_id)
+            .bind(revision_id)
+            .fetch_optional(&self.db)
+            .await?)?;
+        rec.try_into()
+    }
+
+    /// Returns the last revision of each deleted word that hasn't been
+    /// restored, newest first.
+    pub async fn deleted_words(&self) -> Result<Vec<WordRevision>> {
+        let recs: Vec<DbRevision> = sqlx::query_as("
+            SELECT r.id, r.word_id, r.time, r.deleted, r.snapshot
+            FROM word_revision r LEFT JOIN word w ON r.word_id = w.id
+            WHERE r.deleted AND w.id IS NULL
+              AND r.id = (SELECT MAX(id) FROM word_revision WHERE word_id = r.word_id)
+            ORDER BY r.id DESC
+            ")
+            .fetch_all(&self.db)
+            .await?;
+        recs.into_iter().map(|rec| rec.try_into()).collect()
+    }
+
+    /// Returns the changes that restoring a revision would make to the current
+    /// version of the word (or, if given, to another revision).
+    pub async fn diff_word_revision(&self, word_id: i64, revision_id: i64, against: Option<i64>) -> Result<Vec<FieldDiff>> {
+        let revision = self.find_word_revision(word_id, revision_id).await?;
+        let other = match against {
+            Some(id) => Some(self.find_word_revision(word_id, id).await?.word),
+            None => self.find_word_by_id(word_id).await?.as_ref().map(WordSnapshot::from),
+        };
+        diff(other.as_ref(), Some(&revision.word))
+    }
+
+    /// Saves the word as it was at the given revision, recreating it (with the
+    /// same ID) if it was deleted. A revision with a different text than the
+    /// current word isn't restored, since the ID may have been reused for an
+    /// unrelated word before word IDs were made autoincrement. Returns the ID
+    /// of the word.
+    pub async fn restore_word_revision(&self, word_id: i64, revision_id: i64) -> Result<i64> {
+        let revision = self.find_word_revision(word_id, revision_id).await?;
+        let word = revision.word.to_word(word_id);
+        if let Some(current) = self.find_word_by_id(word_id).await? {
+            check(current.text == word.text, "revision has a different text than the current word")?;
+            self.update_word(word_id, &word).await?;
+        } else {
+            self.insert_word(&word).await?;
+        }
+        Ok(word_id)
+    }
+}
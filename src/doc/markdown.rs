@@ -1,3 +1,6 @@
+use std::ops::Range;
+
+use anyhow::anyhow;
 use axum::async_trait;
 use itertools::Itertools;
 use pulldown_cmark::{html::push_html, Event, TextMergeWithOffset};
@@ -22,21 +25,62 @@ impl Parser for MarkdownParser {
 
 pub struct MarkdownHtmlRenderer<T: SnippetRenderer + Send>(pub T);
 
-#[async_trait]
-impl<T: SnippetRenderer + Send + Sync> Renderer for MarkdownHtmlRenderer<T> {
-    async fn render_html(&self, doc: &Document) -> Result<String> {
-        let parser = pulldown_cmark::Parser::new(&doc.text);
-        let parser = TextMergeWithOffset::new(parser.into_offset_iter());
-        let mut events = vec![];
-        for (event, range) in parser {
-            events.push(match event {
+/// Splits the Markdown text into its top-level blocks (e.g., paragraphs,
+/// headings and lists), each with the range of the text it covers and its
+/// events.
+fn blocks(text: &str) -> Vec<(Range<usize>, Vec<(Event<'_>, Range<usize>)>)> {
+    let parser = pulldown_cmark::Parser::new(text);
+    let parser = TextMergeWithOffset::new(parser.into_offset_iter());
+    let mut blocks: Vec<(Range<usize>, Vec<_>)> = vec![];
+    let mut depth = 0;
+    for (event, range) in parser {
+        if depth == 0 {
+            blocks.push((range.clone(), vec![]));
+        }
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {},
+        }
+        if let Some((_, events)) = blocks.last_mut() {
+            events.push((event, range));
+        }
+    }
+    blocks
+}
+
+impl<T: SnippetRenderer + Send + Sync> MarkdownHtmlRenderer<T> {
+    async fn render_events(&self, doc: &Document, events: Vec<(Event<'_>, Range<usize>)>) -> Result<String> {
+        let mut rendered = vec![];
+        for (event, range) in events {
+            rendered.push(match event {
                 Event::Text(_) => Event::InlineHtml(self.0.render_snippet(doc, range).await?.into()),
                 _ => event,
             });
         }
 
         let mut html = String::new();
-        push_html(&mut html, events.into_iter());
+        push_html(&mut html, rendered.into_iter());
         Ok(html)
     }
 }
+
+#[async_trait]
+impl<T: SnippetRenderer + Send + Sync> Renderer for MarkdownHtmlRenderer<T> {
+    async fn render_html(&self, doc: &Document) -> Result<String> {
+        let events = blocks(&doc.text).into_iter()
+            .flat_map(|(_, events)| events)
+            .collect();
+        self.render_events(doc, events).await
+    }
+
+    fn chunks(&self, doc: &Document) -> Vec<Range<usize>> {
+        blocks(&doc.text).into_iter().map(|(range, _)| range).collect()
+    }
+
+    async fn render_chunk(&self, doc: &Document, i: usize, _range: Range<usize>) -> Result<String> {
+        let (_, events) = blocks(&doc.text).into_iter().nth(i)
+            .ok_or_else(|| anyhow!("no such block: {i}"))?;
+        self.render_events(doc, events).await
+    }
+}
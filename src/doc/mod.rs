@@ -28,6 +28,10 @@ impl Document {
     pub fn info<T: 'static + Send + Sync>(&self) -> Option<&T> {
         self.info.get::<T>()
     }
+
+    pub fn info_mut<T: 'static + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.info.get_mut::<T>()
+    }
 }
 
 #[async_trait]
@@ -40,8 +44,23 @@ pub trait Parser: Send {
 }
 
 #[async_trait]
-pub trait Renderer: Send {
+pub trait Renderer: Send + Sync {
     async fn render_html(&self, doc: &Document) -> Result<String>;
+
+    /// Returns the ranges of the document text covered by each chunk of the
+    /// rendered HTML, in order. Chunk `i` may be rendered once the document
+    /// has been analyzed up to the end of its range. By default, the whole
+    /// document is a single chunk.
+    fn chunks(&self, doc: &Document) -> Vec<Range<usize>> {
+        #[allow(clippy::single_range_in_vec_init)]
+        vec![0..doc.text.len()]
+    }
+
+    /// Renders chunk `i` (covering `range`) of the document. Concatenating
+    /// all chunks gives the same result as `render_html`.
+    async fn render_chunk(&self, doc: &Document, _i: usize, _range: Range<usize>) -> Result<String> {
+        self.render_html(doc).await
+    }
 }
 
 pub struct PlainTextParser;
@@ -62,4 +81,17 @@ impl<T: SnippetRenderer + Send + Sync> Renderer for DefaultRenderer<T> {
     async fn render_html(&self, doc: &Document) -> Result<String> {
         self.0.render_snippet(doc, 0..doc.text.len()).await
     }
+
+    fn chunks(&self, doc: &Document) -> Vec<Range<usize>> {
+        let mut start = 0;
+        doc.text.split_inclusive('\n').map(|line| {
+            let range = start..(start + line.len());
+            start = range.end;
+            range
+        }).collect()
+    }
+
+    async fn render_chunk(&self, doc: &Document, _i: usize, range: Range<usize>) -> Result<String> {
+        self.0.render_snippet(doc, range).await
+    }
 }
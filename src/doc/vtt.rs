@@ -163,6 +163,20 @@ fn fmt_cue_time(t: &CueTime) -> String {
     str
 }
 
+impl<'a, T: SnippetRenderer + Send + Sync> VttHtmlRenderer<'a, T> {
+    async fn render_cue(&self, doc: &Document, cue: &Cue, prev_end: &CueTime) -> Result<String> {
+        let mut ctx = tera::Context::new();
+        ctx.insert("prev_end", &prev_end.to_seconds());
+        ctx.insert("start", &cue.start.to_seconds());
+        ctx.insert("end", &cue.end.to_seconds());
+        ctx.insert("prev_end_fmt", &fmt_cue_time(prev_end));
+        ctx.insert("start_fmt", &fmt_cue_time(&cue.start));
+        ctx.insert("end_fmt", &fmt_cue_time(&cue.end));
+        ctx.insert("content", &self.snippets.render_snippet(doc, cue.text_range.clone()).await?);
+        Ok(self.tera.render(&self.cue_template, &ctx)?)
+    }
+}
+
 #[async_trait]
 impl<'a, T: SnippetRenderer + Send + Sync> Renderer for VttHtmlRenderer<'a, T> {
     async fn render_html(&self, doc: &Document) -> Result<String> {
@@ -173,17 +187,27 @@ impl<'a, T: SnippetRenderer + Send + Sync> Renderer for VttHtmlRenderer<'a, T> {
         let mut prev_end = CueTime { seconds: 0, millis: 0 };
         let mut html = String::new();
         for cue in cues {
-            let mut ctx = tera::Context::new();
-            ctx.insert("prev_end", &prev_end.to_seconds());
-            ctx.insert("start", &cue.start.to_seconds());
-            ctx.insert("end", &cue.end.to_seconds());
-            ctx.insert("prev_end_fmt", &fmt_cue_time(&prev_end));
-            ctx.insert("start_fmt", &fmt_cue_time(&cue.start));
-            ctx.insert("end_fmt", &fmt_cue_time(&cue.end));
-            ctx.insert("content", &self.snippets.render_snippet(doc, cue.text_range.clone()).await?);
-            html += &self.tera.render(&self.cue_template, &ctx)?;
+            html += &self.render_cue(doc, cue, &prev_end).await?;
             prev_end = cue.end.clone();
         }
         Ok(html)
     }
+
+    fn chunks(&self, doc: &Document) -> Vec<Range<usize>> {
+        doc.info::<Vec<Cue>>()
+            .map(|cues| cues.iter().map(|cue| cue.text_range.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    async fn render_chunk(&self, doc: &Document, i: usize, _range: Range<usize>) -> Result<String> {
+        let Some(cues) = doc.info::<Vec<Cue>>() else {
+            return Err(anyhow!("not a VTT document").into());
+        };
+        let cue = cues.get(i).ok_or_else(|| anyhow!("no such cue: {i}"))?;
+        let prev_end = match i {
+            0 => CueTime { seconds: 0, millis: 0 },
+            i => cues[i - 1].end.clone(),
+        };
+        self.render_cue(doc, cue, &prev_end).await
+    }
 }
@@ -1,12 +1,14 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
 pub struct Search {
     pub value: String,
     pub regex: bool,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
 pub struct Column {
     pub data: String,
     pub name: String,
@@ -31,6 +33,7 @@ pub struct Order {
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
 pub struct Request {
     pub draw: usize,
     pub start: usize,
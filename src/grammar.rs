@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+
+use crate::{dict::WordStatus, must, not_found, Result};
+
+/// A grammar point (e.g., an ending or connective), tracked like a word. Term
+/// pattern rules refer to grammar points by name, so a point is identified by
+/// its language and name.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct GrammarPoint {
+    pub id: Option<i64>,
+    pub language: String,
+    pub name: String,
+    pub status: WordStatus,
+    pub explanation: String,
+    /// Example sentences, one per line.
+    pub examples: String,
+}
+
+impl GrammarPoint {
+    /// Returns a placeholder for a grammar point that hasn't been added yet.
+    pub fn unknown(language: &str, name: &str) -> Self {
+        Self {
+            id: None,
+            language: language.to_string(),
+            name: name.to_string(),
+            status: WordStatus::Unknown,
+            explanation: String::new(),
+            examples: String::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct GrammarPoints {
+    db: Pool<Sqlite>,
+}
+
+impl GrammarPoints {
+    pub fn new(db: Pool<Sqlite>) -> Self {
+        Self { db }
+    }
+
+    pub async fn list(&self, language: &str) -> Result<Vec<GrammarPoint>> {
+        let points = sqlx::query_as("
+            SELECT id, language, name, status, explanation, examples
+            FROM grammar_point
+            WHERE language = ?
+            ORDER BY name
+            ")
+            .bind(language)
+            .fetch_all(&self.db)
+            .await?;
+        Ok(points)
+    }
+
+    /// Returns the grammar points for the given language, by name.
+    pub async fn by_name(&self, language: &str) -> Result<HashMap<String, GrammarPoint>> {
+        let points = self.list(language).await?;
+        Ok(points.into_iter().map(|p| (p.name.clone(), p)).collect())
+    }
+
+    pub async fn find_by_id(&self, id: i64) -> Result<GrammarPoint> {
+        let point = must(sqlx::query_as("SELECT id, language, name, status, explanation, examples FROM grammar_point WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?)?;
+        Ok(point)
+    }
+
+    pub async fn insert(&self, language: &str, name: &str, status: WordStatus, explanation: &str, examples: &str) -> Result<i64> {
+        let result = sqlx::query("INSERT INTO grammar_point (language, name, status, explanation, examples) VALUES (?, ?, ?, ?, ?)")
+            .bind(language)
+            .bind(name)
+            .bind(status)
+            .bind(explanation)
+            .bind(examples)
+            .execute(&self.db)
+            .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn update(&self, id: i64, name: &str, status: WordStatus, explanation: &str, examples: &str) -> Result<()> {
+        let result = sqlx::query("UPDATE grammar_point SET name = ?, status = ?, explanation = ?, examples = ? WHERE id = ?")
+            .bind(name)
+            .bind(status)
+            .bind(explanation)
+            .bind(examples)
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() != 1 {
+            not_found()?;
+        }
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<()> {
+        let result = sqlx::query("DELETE FROM grammar_point WHERE id = ?")
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() != 1 {
+            not_found()?;
+        }
+        Ok(())
+    }
+}
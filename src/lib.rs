@@ -7,7 +7,10 @@ pub mod config;
 pub mod dict;
 pub mod doc;
 pub mod dt;
+pub mod grammar;
 pub mod morph;
+pub mod overrides;
+pub mod search;
 pub mod vtt;
 
 #[derive(Debug)]
@@ -1,12 +1,12 @@
-use std::{char::REPLACEMENT_CHARACTER, cmp::Ordering, collections::{BTreeMap, HashMap}, io::Cursor, net::{Ipv4Addr, SocketAddrV4}, process::Stdio, str::FromStr, sync::{Arc, LazyLock}};
+use std::{char::REPLACEMENT_CHARACTER, cmp::Ordering, collections::{BTreeMap, HashMap}, convert::Infallible, io::Cursor, net::{Ipv4Addr, SocketAddrV4}, process::Stdio, str::FromStr, sync::{Arc, LazyLock}, time::Duration};
 
 use anyhow::anyhow;
-use axum::{async_trait, body::{Body, Bytes}, extract::{FromRequestParts, Path, Query, State}, http::{header::CONTENT_TYPE, HeaderMap, StatusCode}, response::{Html, IntoResponse, Redirect}, routing::{get, post}, Form, Json, Router};
+use axum::{async_trait, body::{Body, Bytes}, extract::{FromRequestParts, Path, Query, State}, http::{header::CONTENT_TYPE, HeaderMap, StatusCode}, response::{Html, IntoResponse, Redirect}, routing::{get, post, put}, Form, Json, Router};
 use axum_extra::{headers::Range, TypedHeader};
 use axum_range::{KnownSize, Ranged};
 use chrono::{TimeZone, Utc};
 use clap::Parser;
-use futures::{executor::block_on, lock::Mutex};
+use futures::{channel::mpsc, executor::block_on, lock::Mutex, SinkExt, StreamExt};
 use image::{ImageFormat, ImageReader};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -17,7 +17,7 @@ use tokio::{fs::File, io::AsyncReadExt, net::TcpListener, process::Command};
 use tokio_util::io::ReaderStream;
 use tower_http::services::ServeDir;
 
-use lit::{bad_req, books::{Book, Books, NewBook}, check, config::{Config, DisplayConfig}, dict::{Dictionary, Word, WordStatus}, doc::{self, markdown::{MarkdownHtmlRenderer, MarkdownParser}, vtt::{Cue, CueTime, VttHtmlRenderer, VttParser}, DefaultRenderer, Document, Parser as _, PlainTextParser, Renderer, SnippetRenderer}, dt, morph::{analyze_document, Morph, Segment}, must, not_found, status, status_msg, time, Error, Result};
+use lit::{bad_req, books::{Book, Books, NewBook}, check, config::{Config, DisplayConfig}, dict::{bulk::{BulkOperation, BulkSelection}, review::ReviewGrade, Dictionary, Word, WordStatus, EMPTY_WORD}, doc::{self, markdown::{MarkdownHtmlRenderer, MarkdownParser}, vtt::{Cue, CueTime, VttHtmlRenderer, VttParser}, DefaultRenderer, Document, Parser as _, PlainTextParser, Renderer, SnippetRenderer}, dt, grammar::{GrammarPoint, GrammarPoints}, morph::{analyze_document, cache::SegmentCache, hanja::{hanja_chars, HanjaDict}, parse_spans, phrase::{PhraseTrie, Phrases}, sentence::Sentences, Morphs, Segment}, must, not_found, overrides::ParseOverrides, search::{compare_readability, find_sentences, SearchParams, SearchTarget, Sentence}, status, status_msg, time, Error, Result};
 use url::Url;
 use youtube_dl::YoutubeDl;
 
@@ -26,12 +26,20 @@ use youtube_dl::YoutubeDl;
 struct Args {
     #[arg(short, long, help="path to configuration file")]
     config: String,
+
+    #[arg(short, long, help="reload parser rules automatically when their files change")]
+    watch: bool,
 }
 
+/// How often to check for changes to parser rules when `--watch` is set.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
 struct Context {
     config: Config,
-    morph: Morph,
+    morphs: Morphs,
     books: Books,
+    overrides: ParseOverrides,
+    grammar: GrammarPoints,
     dict: Dictionary,
     templates: Arc<Mutex<Tera>>,
     docs: Arc<Mutex<HashMap<i64, Document>>>,
@@ -48,7 +56,12 @@ async fn main() -> Result<()> {
     let books = Books::new(pool.clone(), config.book_audio_path());
     let dict = Dictionary::new(pool.clone(), config.word_images_path());
     time!(dict.prefetch_all().await?);
-    let morph = Morph::load(&config.morph, dict.clone())?;
+    let morphs = Morphs::load(&config, dict.clone(), SegmentCache::new(pool.clone()))?;
+    let overrides = ParseOverrides::new(pool.clone());
+    let grammar = GrammarPoints::new(pool.clone());
+    for lang in morphs.languages() {
+        morphs.set_overrides(lang, &overrides.list(lang, None).await?)?;
+    }
     let port = config.port;
     let mut tera = match Tera::new("templates/**/*.html") {
         Ok(t) => t,
@@ -64,7 +77,20 @@ async fn main() -> Result<()> {
     let templates = Arc::new(Mutex::new(tera));
     let docs = Arc::new(Mutex::new(HashMap::new()));
     let importing = Arc::new(Mutex::new(HashMap::new()));
-    let ctx = Context { config, morph, books, dict, templates, docs, importing };
+    let ctx = Arc::new(Context { config, morphs, books, overrides, grammar, dict, templates, docs, importing });
+    if args.watch {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(WATCH_INTERVAL).await;
+                match reload_morphs(&ctx, false).await {
+                    Ok(langs) if !langs.is_empty() => println!("Reloaded parser rules for: {}", langs.join(", ")),
+                    Ok(_) => (),
+                    Err(e) => println!("WARNING: failed to reload parser rules: {e}"),
+                }
+            }
+        });
+    }
     let app = Router::new()
         .route("/", get(|| async { "Hello, world!" }))
         .route("/import_video", get(get_import_video).post(post_import_video))
@@ -80,19 +106,38 @@ async fn main() -> Result<()> {
         .route("/words/:id/edit", get(edit_word))
         .route("/words/:id/image", get(get_word_image).put(put_word_image).delete(delete_word_image))
         .route("/words/:id/summary", get(get_word_summary))
+        .route("/parse", get(get_parse))
+        .route("/grammar", get(get_grammar))
+        .route("/review", get(get_review))
         .route("/api/imports", get(get_imports))
         .route("/api/words-suggest", get(words_suggest))
         .route("/api/words-dt", get(words_dt))
+        .route("/api/words-timeline", get(words_timeline))
         .route("/api/books-dt", get(books_dt))
         .route("/api/words", get(list_words).post(post_word))
+        .route("/api/words/bulk", post(post_words_bulk))
         .route("/api/words/:id", get(get_word).put(put_word).delete(delete_word))
+        .route("/api/words/:id/history", get(get_word_history))
+        .route("/api/words/:id/revisions", get(list_word_revisions))
+        .route("/api/words/:id/revisions/:revision/diff", get(diff_word_revision))
+        .route("/api/words/:id/revisions/:revision/restore", post(restore_word_revision))
+        .route("/api/words-deleted", get(list_deleted_words))
         .route("/api/books", get(list_books))
         .route("/api/books/:id", get(get_book).patch(patch_book))
         .route("/api/books/:id/read", post(post_book_read))
         .route("/api/books/:id/cues/:ts", get(get_book_cues))
         .route("/api/books/:id/words/:offset", get(get_book_word))
+        .route("/api/parse-overrides", get(list_parse_overrides).post(post_parse_override))
+        .route("/api/parse-overrides/:id", put(put_parse_override).delete(delete_parse_override))
+        .route("/api/grammar", get(list_grammar).post(post_grammar))
+        .route("/api/grammar/:id", get(get_grammar_point).put(put_grammar).delete(delete_grammar))
+        .route("/api/sentences", get(get_sentences))
+        .route("/api/reviews", get(list_reviews))
+        .route("/api/reviews/:word_id", post(post_review))
+        .route("/api/admin/reload", post(post_admin_reload))
+        .route("/api/parse", get(get_api_parse))
         .nest_service("/static", ServeDir::new("static"))
-        .with_state(Arc::new(ctx));
+        .with_state(ctx);
     let addr = SocketAddrV4::new(Ipv4Addr::from_str("0.0.0.0")?, port);
     let listener = TcpListener::bind(addr).await?;
     axum::serve(listener, app).await?;
@@ -165,6 +210,7 @@ async fn lookup_ancestor_words(seg: &Segment, ctx_dict: &Dictionary, display: &D
         words: words.clone(),
         range: seg.range.clone(),
         text: seg.text.clone(),
+        sentence_end: seg.sentence_end,
     };
 
     Ok(WordInfo { seg, dict, deps })
@@ -190,18 +236,46 @@ struct TeraSnippetRenderer<'a> {
     tera: &'a Tera,
     dict: Dictionary,
     display: &'a DisplayConfig,
+    language: &'a str,
+    grammar: HashMap<String, GrammarPoint>,
 }
 
 impl<'a> TeraSnippetRenderer<'a> {
     async fn add_time(&self, dur: std::time::Duration) {
         *self.timing.lock().await += dur;
     }
-}
 
-#[async_trait]
-impl<'a> SnippetRenderer for TeraSnippetRenderer<'a> {
-    async fn render_snippet(&self, doc: &Document, range: std::ops::Range<usize>) -> Result<String> {
-        let mut t = std::time::Duration::ZERO;
+    /// Renders a segment with the given template. `content` is the already
+    /// rendered HTML of the segments inside a phrase.
+    async fn render_segment(&self, seg: &Segment, template: &str, content: Option<&str>, t: &mut std::time::Duration) -> Result<String> {
+        let (min_status, max_status) = self.dict.resolve_stati(seg.words.iter()).await?;
+        let word_info = lookup_ancestor_words(seg, &self.dict, self.display).await?;
+        let words = word_info.dict.get(&seg.text).unwrap();
+
+        let mut ctx = tera::Context::new();
+        ctx.insert("text", &seg.text);
+        ctx.insert("words", &words);
+        ctx.insert("status", &max_status);
+        ctx.insert("min_status", &min_status);
+        ctx.insert("dict", &word_info.dict);
+        ctx.insert("deps", &word_info.deps);
+        let grammar = seg.words.iter()
+            .flat_map(|w| w.grammar.iter())
+            .unique()
+            .map(|name| self.grammar.get(name).cloned().unwrap_or_else(|| GrammarPoint::unknown(self.language, name)))
+            .collect_vec();
+        ctx.insert("grammar", &grammar);
+        if let Some(content) = content {
+            ctx.insert("content", content);
+        }
+        let mut dur = std::time::Duration::ZERO;
+        let html = time!(dur, self.tera.render(template, &ctx)?).trim().to_string();
+        *t += dur;
+        Ok(html)
+    }
+
+    /// Renders the text and segments in the given range, ignoring phrases.
+    async fn render_words(&self, doc: &Document, range: std::ops::Range<usize>, t: &mut std::time::Duration) -> Result<String> {
         let words: &BTreeMap<usize, Segment> = doc.info()
             .ok_or_else(|| anyhow!("document analysis missing"))?;
         let mut pos = range.start;
@@ -211,24 +285,34 @@ impl<'a> SnippetRenderer for TeraSnippetRenderer<'a> {
                 result += &doc.text[pos..start].replace("\n", "<br>"); // FIXME: HTML-escape
             }
             pos = seg.range.end;
-
-            let (min_status, max_status) = self.dict.resolve_stati(seg.words.iter()).await?;
-            let word_info = lookup_ancestor_words(seg, &self.dict, &self.display).await?;
-            let words = word_info.dict.get(&seg.text).unwrap();
-
-            let mut ctx = tera::Context::new();
-            ctx.insert("text", &seg.text);
-            ctx.insert("words", &words);
-            ctx.insert("status", &max_status);
-            ctx.insert("min_status", &min_status);
-            ctx.insert("dict", &word_info.dict);
-            ctx.insert("deps", &word_info.deps);
-            result += time!(t, self.tera.render("inline_word.html", &ctx)?).trim();
+            result += &self.render_segment(seg, "inline_word.html", None, t).await?;
         }
         let end = range.end;
         if pos < end {
             result += &doc.text[pos..end].replace("\n", "<br>"); // FIXME: HTML-scape
         }
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl<'a> SnippetRenderer for TeraSnippetRenderer<'a> {
+    async fn render_snippet(&self, doc: &Document, range: std::ops::Range<usize>) -> Result<String> {
+        let mut t = std::time::Duration::ZERO;
+        let mut pos = range.start;
+        let mut result = String::new();
+        if let Some(Phrases(phrases)) = doc.info() {
+            for (&start, phrase) in phrases.range(range.clone()) {
+                if start < pos || phrase.range.end > range.end {
+                    continue;
+                }
+                result += &self.render_words(doc, pos..start, &mut t).await?;
+                let content = self.render_words(doc, phrase.range.clone(), &mut t).await?;
+                result += &self.render_segment(phrase, "inline_phrase.html", Some(&content), &mut t).await?;
+                pos = phrase.range.end;
+            }
+        }
+        result += &self.render_words(doc, pos..range.end, &mut t).await?;
         block_on(self.add_time(t));
         Ok(result)
     }
@@ -266,6 +350,7 @@ async fn get_edit(
     tera.insert("title", &book.title);
     tera.insert("content_type", &book.content_type);
     tera.insert("content", &content);
+    tera.insert("language", ctx.config.language(book.language.as_deref())?.0);
     tera.insert("audio_format", &book.audio_file.map(|_| "audio/mpeg")); // FIXME
     tera.insert("url", &book.url);
     tera.insert("youtube_video_id", &book.url.and_then(|url| youtube_video_id(url.as_str())));
@@ -274,71 +359,125 @@ async fn get_edit(
     Ok(resp)
 }
 
+/// Placeholder for the book content in the rendered "read.html" template, which
+/// is replaced by the content as it is streamed.
+const READ_CONTENT_PLACEHOLDER: &str = "<!--lit:content-->";
+
 #[axum::debug_handler]
 async fn read(
     State(ctx): State<Arc<Context>>,
     Path(slug): Path<String>,
 ) -> Result<impl IntoResponse> {
     ctx.templates.lock().await.full_reload()?;
-    let now = std::time::Instant::now();
     let book = ctx.books.find_book_by_slug(slug).await?;
-    dbg!(now.elapsed());
-    let title = book.title;
 
-    let tera = ctx.templates.lock().await;
-    let snippets = TeraSnippetRenderer {
-        tera: &tera,
-        dict: ctx.dict.clone(),
-        display: &ctx.config.display,
-        timing: Arc::default(),
-    };
-    
-    let (parser, renderer): (Box<dyn doc::Parser>, Box<dyn doc::Renderer>) = match book.content_type.as_str() {
-        "text/plain" => (Box::new(PlainTextParser), Box::new(DefaultRenderer(snippets))),
-        "text/vtt" => (Box::new(VttParser), Box::new(VttHtmlRenderer { tera: &tera, snippets, cue_template: "vtt_cue.html".to_string() })),
-        "text/markdown" => (Box::new(MarkdownParser), Box::new(MarkdownHtmlRenderer(snippets))),
+    let parser: Box<dyn doc::Parser> = match book.content_type.as_str() {
+        "text/plain" => Box::new(PlainTextParser),
+        "text/vtt" => Box::new(VttParser),
+        "text/markdown" => Box::new(MarkdownParser),
         t => return bad_req(format!("invalid book content type: {t}").as_str()),
     };
-    
-    dbg!(now.elapsed());
     let document = parser.parse_document(&book.content)?;
-    dbg!(now.elapsed());
-    let document = analyze_document(document, &ctx.morph, &ctx.dict).await?;
-    dbg!(now.elapsed());
-    // let document = compute_document_stats(&ctx, document).await?;
-    // dbg!(document.info::<DocumentStats>());
-    // dbg!(now.elapsed());
-    
-    dbg!("rendering html");
-    let content = renderer.render_html(&document).await?;
-    // dbg!(*snippets.timing.lock().await);
-    drop(renderer);
-    drop(tera);
-    dbg!("done rendering html");
-    dbg!(now.elapsed());
-
-    // let content = match book.content_type.as_str() {
-    //     "text/plain" => render_text_ko(&ctx, &book.content).await?,
-    //     "text/vtt" => render_vtt_book(&ctx, &book.content).await?,
-    //     "text/markdown" => render_markdown_book(&ctx, &book.content).await?,
-    //     t => return bad_req(format!("invalid book content type: {t}").as_str()),
-    // };
+    let (language, _) = ctx.config.language(book.language.as_deref())?;
+    let morph = ctx.morphs.get(book.language.as_deref())?;
 
     let mut tera = tera::Context::new();
     tera.insert("id", &book.id);
-    tera.insert("title", &title);
+    tera.insert("title", &book.title);
     tera.insert("content_type", &book.content_type);
-    tera.insert("content", &content);
-    tera.insert("audio_format", &book.audio_file.map(|_| "audio/mpeg")); // FIXME
+    tera.insert("content", READ_CONTENT_PLACEHOLDER);
+    tera.insert("language", language);
+    tera.insert("parse_overrides", &morph.supports_overrides());
+    tera.insert("audio_format", &book.audio_file.as_ref().map(|_| "audio/mpeg")); // FIXME
     tera.insert("url", &book.url);
-    tera.insert("youtube_video_id", &book.url.and_then(|url| youtube_video_id(url.as_str())));
-    dbg!("rendering template");
-    let resp = Html(ctx.templates.lock().await.render("read.html", &tera)?);
-    dbg!(now.elapsed());
+    tera.insert("youtube_video_id", &book.url.as_ref().and_then(|url| youtube_video_id(url.as_str())));
+    let page = ctx.templates.lock().await.render("read.html", &tera)?;
+    let Some((head, tail)) = page.split_once(READ_CONTENT_PLACEHOLDER) else {
+        return Err(anyhow!("read.html does not include content").into());
+    };
+    let (head, tail) = (head.to_string(), tail.to_string());
 
-    dbg!("done read");
+    let (mut tx, rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        let now = std::time::Instant::now();
+        if tx.send(head).await.is_err() {
+            return;
+        }
+        if let Err(err) = stream_book_content(&ctx, &book, document, &mut tx).await {
+            let msg = tera::escape_html(&err.to_string());
+            let _ = tx.send(format!(r#"<div class="error">Could not render book: {msg}</div>"#)).await;
+        }
+        let _ = tx.send(tail).await;
+        dbg!(now.elapsed());
+    });
 
-    Ok(resp)
+    let body = Body::from_stream(rx.map(Ok::<_, Infallible>));
+    Ok(([(CONTENT_TYPE, "text/html; charset=utf-8")], body))
+}
+
+/// Analyzes and renders the book content, sending each chunk of HTML (e.g., a
+/// cue or a paragraph) as soon as the text it covers has been analyzed.
+async fn stream_book_content(ctx: &Context, book: &Book, document: Document, tx: &mut mpsc::Sender<String>) -> Result<()> {
+    let tera = ctx.templates.lock().await.clone();
+    let (language, _) = ctx.config.language(book.language.as_deref())?;
+    let dict = ctx.dict.for_language(language);
+    let snippets = TeraSnippetRenderer {
+        tera: &tera,
+        dict: dict.clone(),
+        display: &ctx.config.display,
+        language,
+        grammar: ctx.grammar.by_name(language).await?,
+        timing: Arc::default(),
+    };
+    let renderer: Box<dyn doc::Renderer> = match book.content_type.as_str() {
+        "text/plain" => Box::new(DefaultRenderer(snippets)),
+        "text/vtt" => Box::new(VttHtmlRenderer { tera: &tera, snippets, cue_template: "vtt_cue.html".to_string() }),
+        "text/markdown" => Box::new(MarkdownHtmlRenderer(snippets)),
+        t => return bad_req(format!("invalid book content type: {t}").as_str()),
+    };
+
+    let text = document.text.clone();
+    let spans = document.spans.clone();
+    let phrase_trie = PhraseTrie::load(&dict).await?;
+    let mut document = document
+        .with(BTreeMap::<usize, Segment>::new())
+        .with(Phrases::default());
+    let mut segs = Box::pin(ctx.morphs.analyze_book_stream(book.id, book.language.as_deref(), &text, &spans, &dict));
+    let mut analyzed = 0;
+    let mut done = false;
+    for (i, range) in renderer.chunks(&document).into_iter().enumerate() {
+        while !done && analyzed < range.end {
+            let Some(seg) = segs.next().await else {
+                done = true;
+                break;
+            };
+            let seg = seg?;
+            analyzed = seg.range.end;
+            document.info_mut::<BTreeMap<usize, Segment>>()
+                .ok_or_else(|| anyhow!("document analysis missing"))?
+                .insert(seg.range.start, seg);
+        }
+        if !phrase_trie.is_empty() {
+            let mut phrases = vec![];
+            let chunk_segs = document.info::<BTreeMap<usize, Segment>>()
+                .ok_or_else(|| anyhow!("document analysis missing"))?;
+            for span in spans.iter().filter(|span| span.start < range.end && range.start < span.end) {
+                let span = span.start.max(range.start)..span.end.min(range.end);
+                phrases.extend(phrase_trie.find(&text, chunk_segs, span, &dict).await?);
+            }
+            let doc_phrases = document.info_mut::<Phrases>()
+                .ok_or_else(|| anyhow!("document analysis missing"))?;
+            for phrase in phrases {
+                doc_phrases.0.insert(phrase.range.start, phrase);
+            }
+        }
+        let html = renderer.render_chunk(&document, i, range).await?;
+        if tx.send(html).await.is_err() {
+            // The client went away.
+            return Ok(());
+        }
+    }
+    Ok(())
 }
 
 fn is_base64_digit(c: char) -> bool {
@@ -435,18 +574,37 @@ async fn get_books(State(ctx): State<Arc<Context>>) -> Result<impl IntoResponse>
     Ok(Html(ctx.templates.lock().await.render("books.html", &tera)?))
 }
 
+#[derive(Clone, Debug, Deserialize)]
+struct LanguageRequest {
+    lang: Option<String>,
+}
+
 async fn edit_define(
     State(ctx): State<Arc<Context>>,
     Path(texts): Path<String>,
+    Query(req): Query<LanguageRequest>,
 ) -> Result<impl IntoResponse> {
+    let (lang, lang_config) = ctx.config.language(req.lang.as_deref())?;
     let texts = texts.split(',').map(|s| s.to_string()).collect::<Vec<_>>();
     ctx.templates.lock().await.full_reload()?;
     let mut tera = tera::Context::new();
-    let dict = ctx.dict.find_word_trees_by_text(texts.clone()).await?;
+    let lang_dict = ctx.dict.for_language(lang);
+    let dict = lang_dict.find_word_trees_by_text(texts.clone()).await?;
+    let mut hanja_info = HashMap::new();
+    if let Some(hanja) = ctx.morphs.get(Some(lang)).ok().and_then(|m| m.hanja()) {
+        for text in texts.iter() {
+            if let Some(spelling) = hanja.display(text) {
+                let components = hanja_components(&lang_dict, &hanja, &spelling, text).await?;
+                hanja_info.insert(text.clone(), HanjaInfo { spelling, components });
+            }
+        }
+    }
     tera.insert("texts", &texts);
+    tera.insert("hanja", &hanja_info);
     tera.insert("dict", &dict);
     tera.insert("all_tags", &ctx.dict.all_tags().await?);
-    tera.insert("dictionaries", &ctx.config.dictionaries);
+    tera.insert("language", lang);
+    tera.insert("dictionaries", &lang_config.dictionaries);
     Ok(Html(ctx.templates.lock().await.render("edit_define.html", &tera)?))
 }
 
@@ -462,14 +620,83 @@ async fn edit_word(
     Ok(Html(ctx.templates.lock().await.render("edit_word.html", &tera)?))
 }
 
+/// The most words to list for each Hanja character on the define page.
+const MAX_HANJA_WORDS: usize = 20;
+
+#[derive(Clone, Debug, Serialize)]
+struct HanjaComponent {
+    character: char,
+    words: Vec<Word>,
+}
+
+/// Returns the known words other than `text` which are spelled with each of
+/// the Hanja characters in `spellings`.
+async fn hanja_components(dict: &Dictionary, hanja: &HanjaDict, spellings: &str, text: &str) -> Result<Vec<HanjaComponent>> {
+    let characters = hanja_chars(spellings);
+    let candidates = characters.iter()
+        .flat_map(|c| hanja.words_with(*c))
+        .filter(|other| *other != text)
+        .map(|other| other.as_str())
+        .unique()
+        .collect_vec();
+    let existing = dict.existing_texts(&candidates).await?;
+    let mut components = vec![];
+    for character in characters {
+        let mut component = HanjaComponent { character, words: vec![] };
+        for other in hanja.words_with(character) {
+            if component.words.len() >= MAX_HANJA_WORDS {
+                break;
+            }
+            if !existing.contains(other) {
+                continue;
+            }
+            for mut word in dict.find_words_by_text(other).await? {
+                let status = dict.resolve_status(&word).await?;
+                if matches!(status.1, WordStatus::Unknown | WordStatus::Ignored) {
+                    continue;
+                }
+                word.resolved_status = Some(status);
+                word.hanja = hanja.display(other);
+                component.words.push(word);
+                break;
+            }
+        }
+        components.push(component);
+    }
+    Ok(components)
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct HanjaInfo {
+    spelling: String,
+    components: Vec<HanjaComponent>,
+}
+
 async fn define(
     State(ctx): State<Arc<Context>>,
     Path(text): Path<String>,
+    Query(req): Query<LanguageRequest>,
 ) -> Result<impl IntoResponse> {
-    let words = ctx.dict.find_words_by_text(&text).await?;
+    let (lang, _) = ctx.config.language(req.lang.as_deref())?;
+    let dict = ctx.dict.for_language(lang);
+    let mut words = dict.find_words_by_text(&text).await?;
+    let mut components = vec![];
+    if let Some(hanja) = ctx.morphs.get(Some(lang)).ok().and_then(|m| m.hanja()) {
+        for word in words.iter_mut() {
+            word.hanja = hanja.display(&word.text);
+        }
+        if let Some(spelling) = hanja.display(&text) {
+            components = hanja_components(&dict, &hanja, &spelling, &text).await?;
+        }
+    }
     let mut tera = tera::Context::new();
     tera.insert("words", &words);
-    let html = ctx.templates.lock().await.render("define.html", &tera)?;
+    tera.insert("hanja_components", &components);
+    tera.insert("language", lang);
+    let mut html = ctx.templates.lock().await.render("define.html", &tera)?;
+    if !components.is_empty() {
+        html += &ctx.templates.lock().await.render("define_hanja.html", &tera)?;
+    }
     Ok(Html(format!(r#"
         <html>
             <head>
@@ -595,13 +822,18 @@ async fn get_word_image(
 #[derive(Clone, Debug, Deserialize)]
 struct WordSearch {
     text: String,
+    lang: Option<String>,
 }
 
 async fn list_words(
     State(ctx): State<Arc<Context>>,
     Query(search): Query<WordSearch>,
 ) -> Result<impl IntoResponse> {
-    let words = ctx.dict.find_words_by_text(&search.text).await?;
+    let dict = match search.lang.as_deref() {
+        Some(lang) => ctx.dict.for_language(ctx.config.language(Some(lang))?.0),
+        None => ctx.dict.clone(),
+    };
+    let words = dict.find_words_by_text(&search.text).await?;
     if words.is_empty() {
         not_found()?;
     }
@@ -659,6 +891,72 @@ async fn delete_word(
     ctx.dict.delete_word(id).await
 }
 
+#[derive(Clone, Debug, Deserialize)]
+struct BulkRequest {
+    words: BulkSelection,
+    operation: BulkOperation,
+}
+
+async fn post_words_bulk(
+    State(ctx): State<Arc<Context>>,
+    Json(req): Json<BulkRequest>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(ctx.dict.apply_bulk(req.words, &req.operation).await?))
+}
+
+async fn get_word_history(
+    State(ctx): State<Arc<Context>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    must(ctx.dict.find_word_by_id(id).await?)?;
+    Ok(Json(ctx.dict.word_history(id).await?))
+}
+
+async fn list_word_revisions(
+    State(ctx): State<Arc<Context>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    let revisions = ctx.dict.word_revisions(id).await?;
+    if revisions.is_empty() {
+        not_found()?;
+    }
+    Ok(Json(revisions))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct RevisionDiffRequest {
+    against: Option<i64>,
+}
+
+async fn diff_word_revision(
+    State(ctx): State<Arc<Context>>,
+    Path((id, revision)): Path<(i64, i64)>,
+    Query(req): Query<RevisionDiffRequest>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(ctx.dict.diff_word_revision(id, revision, req.against).await?))
+}
+
+async fn restore_word_revision(
+    State(ctx): State<Arc<Context>>,
+    Path((id, revision)): Path<(i64, i64)>,
+) -> Result<impl IntoResponse> {
+    ctx.dict.restore_word_revision(id, revision).await?;
+    must(ctx.dict.find_word_by_id(id).await?).map(Json)
+}
+
+async fn list_deleted_words(
+    State(ctx): State<Arc<Context>>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(ctx.dict.deleted_words().await?))
+}
+
+async fn words_timeline(
+    State(ctx): State<Arc<Context>>,
+    Query(req): Query<LanguageRequest>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(ctx.dict.status_timeline(req.lang.as_deref()).await?))
+}
+
 #[derive(Clone, Debug, Deserialize)]
 struct BookSearch {
     url: String,
@@ -814,17 +1112,19 @@ async fn get_book_cues(
     State(ctx): State<Arc<Context>>,
     Path((id, ts)): Path<(i64, f64)>,
 ) -> Result<impl IntoResponse> {
+    let book = ctx.books.find_book_by_id(id).await?;
+    let (language, _) = ctx.config.language(book.language.as_deref())?;
+    let dict = ctx.dict.for_language(language);
     let mut docs = ctx.docs.lock().await;
 
     let doc = match docs.get(&id) {
         Some(doc) => doc,
         None => {
-            let book = ctx.books.find_book_by_id(id).await?;
             if book.content_type != "text/vtt" {
                 return bad_req("invalid book type");
             }
             let doc = VttParser.parse_document(&book.content)?;
-            let doc = analyze_document(doc, &ctx.morph, &ctx.dict).await?;
+            let doc = ctx.morphs.analyze_book(book.id, book.language.as_deref(), doc, &dict).await?;
             docs.insert(id, doc);
             docs.get(&id).unwrap()
         },
@@ -853,7 +1153,7 @@ async fn get_book_cues(
     let book_cues = cues;
     let mut cues = vec![];
     for cue in book_cues[min..=max].iter() {
-        cues.push(render_book_cue(doc, cue, &ctx.dict, &ctx.config.display).await?);
+        cues.push(render_book_cue(doc, cue, &dict, &ctx.config.display).await?);
     }
 
     Ok(Json(BookCues { cues }))
@@ -881,6 +1181,8 @@ struct BookWordDef {
     pub inherit: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub debug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hanja: Option<String>,
 }
 
 impl From<Word> for BookWordDef {
@@ -901,6 +1203,7 @@ impl From<Word> for BookWordDef {
             resolved_status: value.resolved_status,
             inherit: value.inherit,
             debug: value.debug,
+            hanja: value.hanja,
         }
     }
 }
@@ -914,23 +1217,28 @@ struct BookWord {
     defs: Vec<BookWordDef>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     deps: Vec<BookWordDef>,
+    /// The sentence containing the word.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sentence: Option<String>,
 }
 
 async fn get_book_word(
     State(ctx): State<Arc<Context>>,
     Path((id, offset)): Path<(i64, usize)>,
 ) -> Result<impl IntoResponse> {
+    let book = ctx.books.find_book_by_id(id).await?;
+    let (language, _) = ctx.config.language(book.language.as_deref())?;
+    let dict = ctx.dict.for_language(language);
     let mut docs = ctx.docs.lock().await;
 
     let doc = match docs.get(&id) {
         Some(doc) => doc,
         None => {
-            let book = ctx.books.find_book_by_id(id).await?;
             if book.content_type != "text/vtt" {
                 return bad_req("invalid book type");
             }
             let doc = VttParser.parse_document(&book.content)?;
-            let doc = analyze_document(doc, &ctx.morph, &ctx.dict).await?;
+            let doc = ctx.morphs.analyze_book(book.id, book.language.as_deref(), doc, &dict).await?;
             docs.insert(id, doc);
             docs.get(&id).unwrap()
         },
@@ -941,8 +1249,8 @@ async fn get_book_word(
     };
 
     let seg = must(words.get(&offset))?;
-    let word_info = lookup_ancestor_words(seg, &ctx.dict, &ctx.config.display).await?;
-    let (min_status, max_status) = ctx.dict.resolve_stati(word_info.seg.words.iter()).await?;
+    let word_info = lookup_ancestor_words(seg, &dict, &ctx.config.display).await?;
+    let (min_status, max_status) = dict.resolve_stati(word_info.seg.words.iter()).await?;
 
     let word_ids = word_info.seg.words.iter().filter_map(|w| w.id).collect_vec();
 
@@ -956,6 +1264,9 @@ async fn get_book_word(
             .map(|w| w.into())
             .collect_vec(),
         min_status, max_status,
+        sentence: doc.info::<Sentences>()
+            .and_then(|sentences| sentences.containing(offset))
+            .map(|range| doc.text[range].to_string()),
     };
 
     Ok(Json(word))
@@ -964,6 +1275,7 @@ async fn get_book_word(
 #[derive(Clone, Debug, Deserialize)]
 struct PatchBookRequest {
     content: Option<String>,
+    language: Option<String>,
 }
 
 async fn patch_book(
@@ -974,14 +1286,462 @@ async fn patch_book(
     if let Some(content) = book.content {
         ctx.books.set_book_content(id, content.as_str()).await?;
     }
+    if let Some(language) = book.language {
+        if !language.is_empty() {
+            ctx.config.language(Some(&language))?;
+        }
+        ctx.books.set_book_language(id, Some(&language)).await?;
+        ctx.docs.lock().await.remove(&id);
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ParseOverrideSearch {
+    lang: Option<String>,
+    word: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ParseOverrideRequest {
+    lang: Option<String>,
+    word: String,
+    parsing: String,
+}
+
+/// Loads the parse overrides for the given language into its parser, and
+/// discards analyzed documents so that the change is visible right away.
+async fn refresh_parse_overrides(ctx: &Context, lang: &str) -> Result<()> {
+    let overrides = ctx.overrides.list(lang, None).await?;
+    ctx.morphs.set_overrides(lang, &overrides)?;
+    ctx.docs.lock().await.clear();
     Ok(())
 }
 
+async fn list_parse_overrides(
+    State(ctx): State<Arc<Context>>,
+    Query(search): Query<ParseOverrideSearch>,
+) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(search.lang.as_deref())?;
+    Ok(Json(ctx.overrides.list(lang, search.word.as_deref()).await?))
+}
+
+async fn post_parse_override(
+    State(ctx): State<Arc<Context>>,
+    Json(req): Json<ParseOverrideRequest>,
+) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(req.lang.as_deref())?;
+    ctx.morphs.get(Some(lang))?.validate_override(&req.word, &req.parsing)?;
+    let id = ctx.overrides.insert(lang, &req.word, &req.parsing).await?;
+    refresh_parse_overrides(&ctx, lang).await?;
+    Ok(Json(ctx.overrides.find_by_id(id).await?))
+}
+
+async fn put_parse_override(
+    State(ctx): State<Arc<Context>>,
+    Path(id): Path<i64>,
+    Json(req): Json<ParseOverrideRequest>,
+) -> Result<impl IntoResponse> {
+    let ovr = ctx.overrides.find_by_id(id).await?;
+    if let Some(lang) = req.lang {
+        check(lang == ovr.language, "language cannot be changed")?;
+    }
+    ctx.morphs.get(Some(&ovr.language))?.validate_override(&req.word, &req.parsing)?;
+    ctx.overrides.update(id, &req.word, &req.parsing).await?;
+    refresh_parse_overrides(&ctx, &ovr.language).await
+}
+
+async fn delete_parse_override(
+    State(ctx): State<Arc<Context>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    let ovr = ctx.overrides.find_by_id(id).await?;
+    ctx.overrides.delete(id).await?;
+    refresh_parse_overrides(&ctx, &ovr.language).await
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct GrammarPointRequest {
+    lang: Option<String>,
+    name: String,
+    status: Option<WordStatus>,
+    #[serde(default)]
+    explanation: String,
+    #[serde(default)]
+    examples: String,
+}
+
+/// Validates the request, returning the status of the grammar point (new, if
+/// unspecified).
+fn validate_grammar_point(req: &GrammarPointRequest) -> Result<WordStatus> {
+    check(!req.name.trim().is_empty(), "name must not be empty")?;
+    check(req.name.trim() == req.name, "name must not have leading or trailing whitespace")?;
+    let status = req.status.unwrap_or(WordStatus::New);
+    check(status != WordStatus::Unknown, "status must not be unknown")?;
+    Ok(status)
+}
+
+/// Returns the grammar points for the given language, including those that
+/// the parser's rules refer to but that haven't been added yet.
+async fn all_grammar_points(ctx: &Context, lang: &str) -> Result<Vec<GrammarPoint>> {
+    let mut points = ctx.grammar.by_name(lang).await?;
+    for name in ctx.morphs.get(Some(lang))?.grammar_points() {
+        points.entry(name.clone()).or_insert_with(|| GrammarPoint::unknown(lang, &name));
+    }
+    Ok(points.into_values().sorted_by(|a, b| a.name.cmp(&b.name)).collect())
+}
+
+async fn get_grammar(
+    State(ctx): State<Arc<Context>>,
+    Query(req): Query<LanguageRequest>,
+) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(req.lang.as_deref())?;
+    ctx.templates.lock().await.full_reload()?;
+    let mut tera = tera::Context::new();
+    tera.insert("language", lang);
+    tera.insert("languages", &ctx.morphs.languages().sorted().collect_vec());
+    tera.insert("points", &all_grammar_points(&ctx, lang).await?);
+    Ok(Html(ctx.templates.lock().await.render("grammar.html", &tera)?))
+}
+
+async fn list_grammar(
+    State(ctx): State<Arc<Context>>,
+    Query(req): Query<LanguageRequest>,
+) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(req.lang.as_deref())?;
+    Ok(Json(all_grammar_points(&ctx, lang).await?))
+}
+
+async fn get_grammar_point(
+    State(ctx): State<Arc<Context>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(ctx.grammar.find_by_id(id).await?))
+}
+
+async fn post_grammar(
+    State(ctx): State<Arc<Context>>,
+    Json(req): Json<GrammarPointRequest>,
+) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(req.lang.as_deref())?;
+    let status = validate_grammar_point(&req)?;
+    let id = ctx.grammar.insert(lang, &req.name, status, &req.explanation, &req.examples).await?;
+    Ok(Json(ctx.grammar.find_by_id(id).await?))
+}
+
+async fn put_grammar(
+    State(ctx): State<Arc<Context>>,
+    Path(id): Path<i64>,
+    Json(req): Json<GrammarPointRequest>,
+) -> Result<impl IntoResponse> {
+    let point = ctx.grammar.find_by_id(id).await?;
+    if let Some(lang) = req.lang.as_ref() {
+        check(*lang == point.language, "language cannot be changed")?;
+    }
+    let status = validate_grammar_point(&req)?;
+    ctx.grammar.update(id, &req.name, status, &req.explanation, &req.examples).await
+}
+
+async fn delete_grammar(
+    State(ctx): State<Arc<Context>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    ctx.grammar.delete(id).await
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct SentenceSearch {
+    lang: Option<String>,
+    word: Option<String>,
+    grammar: Option<String>,
+    tag: Option<String>,
+    count: Option<usize>,
+    min_words: Option<usize>,
+    max_words: Option<usize>,
+    #[serde(default)]
+    only_read: bool,
+    /// The number of books to search, most recently read first.
+    books: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct SentenceResult {
+    book_id: i64,
+    slug: String,
+    title: String,
+    #[serde(flatten)]
+    sentence: Sentence,
+}
+
+/// Searches the books in a language for sentences containing a word, grammar
+/// point or tag, returning the most readable ones first.
+async fn get_sentences(
+    State(ctx): State<Arc<Context>>,
+    Query(req): Query<SentenceSearch>,
+) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(req.lang.as_deref())?;
+    let target = match (req.word, req.grammar, req.tag) {
+        (Some(word), None, None) => SearchTarget::Word(word),
+        (None, Some(grammar), None) => SearchTarget::Grammar(grammar),
+        (None, None, Some(tag)) => SearchTarget::Tag(tag),
+        _ => return bad_req("exactly one of word, grammar or tag must be given"),
+    };
+    let params = SearchParams {
+        target,
+        min_sentence_words: req.min_words.unwrap_or(5),
+        max_sentence_words: req.max_words.unwrap_or(21),
+    };
+    check(params.max_sentence_words >= 1, "max_words must be at least 1")?;
+    check(params.min_sentence_words <= params.max_sentence_words, "min_words must not exceed max_words")?;
+    let max_books = req.books.unwrap_or(50);
+    check(max_books >= 1, "books must be at least 1")?;
+
+    let dict = ctx.dict.for_language(lang);
+    let mut results = vec![];
+    let mut searched = 0;
+    let mut books = Box::pin(ctx.books.recently_read_books());
+    while let Some(book) = books.next().await {
+        let book = book?;
+        if ctx.config.language(book.language.as_deref())?.0 != lang {
+            continue;
+        }
+        if req.only_read && book.last_read.is_none() {
+            continue;
+        }
+        if searched == max_books {
+            break;
+        }
+        searched += 1;
+        let parser: Box<dyn doc::Parser> = match book.content_type.as_str() {
+            "text/plain" => Box::new(PlainTextParser),
+            "text/vtt" => Box::new(VttParser),
+            "text/markdown" => Box::new(MarkdownParser),
+            t => return bad_req(format!("invalid book content type: {t}").as_str()),
+        };
+        let doc = parser.parse_document(&book.content)?;
+        let doc = ctx.morphs.analyze_book(book.id, book.language.as_deref(), doc, &dict).await?;
+        for sentence in find_sentences(&params, &dict, &doc).await? {
+            results.push(SentenceResult {
+                book_id: book.id,
+                slug: book.slug.clone(),
+                title: book.title.clone(),
+                sentence,
+            });
+        }
+    }
+
+    results.sort_by(|a, b| compare_readability(&b.sentence, &a.sentence));
+    results.truncate(req.count.unwrap_or(10));
+    Ok(Json(results))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ReviewSearch {
+    lang: Option<String>,
+    limit: Option<usize>,
+    new: Option<usize>,
+}
+
+async fn list_reviews(
+    State(ctx): State<Arc<Context>>,
+    Query(req): Query<ReviewSearch>,
+) -> Result<impl IntoResponse> {
+    let cards = ctx.dict.due_reviews(req.lang.as_deref(), req.limit.unwrap_or(50), req.new.unwrap_or(10)).await?;
+    Ok(Json(cards))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ReviewRequest {
+    grade: ReviewGrade,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ReviewResponse {
+    status: WordStatus,
+    due: String,
+    interval_days: f64,
+}
+
+async fn post_review(
+    State(ctx): State<Arc<Context>>,
+    Path(word_id): Path<i64>,
+    Json(req): Json<ReviewRequest>,
+) -> Result<impl IntoResponse> {
+    let state = ctx.dict.review_word(word_id, req.grade).await?;
+    Ok(Json(ReviewResponse {
+        status: state.status(),
+        due: state.due.to_rfc3339(),
+        interval_days: state.interval_days,
+    }))
+}
+
+async fn get_review(
+    State(ctx): State<Arc<Context>>,
+    Query(req): Query<LanguageRequest>,
+) -> Result<impl IntoResponse> {
+    ctx.templates.lock().await.full_reload()?;
+    let mut tera = tera::Context::new();
+    tera.insert("language", &req.lang.filter(|lang| !lang.is_empty()));
+    tera.insert("languages", &ctx.morphs.languages().sorted().collect_vec());
+    Ok(Html(ctx.templates.lock().await.render("review.html", &tera)?))
+}
+
+/// Reloads parser rules (see `Morphs::reload`), and discards analyzed
+/// documents if any parsers were reloaded.
+async fn reload_morphs(ctx: &Context, force: bool) -> Result<Vec<String>> {
+    let langs = ctx.morphs.reload(force)?;
+    if !langs.is_empty() {
+        ctx.docs.lock().await.clear();
+    }
+    Ok(langs)
+}
+
+async fn post_admin_reload(
+    State(ctx): State<Arc<Context>>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(reload_morphs(&ctx, true).await?))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ParseRequest {
+    text: String,
+    lang: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ParsedSegment {
+    range: std::ops::Range<usize>,
+    text: String,
+    /// The candidate words after looking the segment up in the dictionary.
+    words: Vec<Word>,
+    /// The words produced by the parser, including its debug trace.
+    parsed: Vec<Word>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ParseResponse {
+    language: String,
+    segments: Vec<ParsedSegment>,
+    phrases: Vec<ParsedSegment>,
+}
+
+/// Runs the parser for the given language on arbitrary text, for debugging
+/// the parser and its rules.
+async fn get_api_parse(
+    State(ctx): State<Arc<Context>>,
+    Query(req): Query<ParseRequest>,
+) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(req.lang.as_deref())?;
+    let morph = ctx.morphs.get(Some(lang))?;
+    let dict = ctx.dict.for_language(lang);
+    let doc = PlainTextParser.parse_document(&req.text)?;
+
+    let mut parsed = HashMap::new();
+    {
+        let mut segs = Box::pin(parse_spans(&doc.text, &doc.spans, morph));
+        while let Some(seg) = segs.next().await {
+            let seg = seg?;
+            parsed.insert(seg.range.start, seg.words);
+        }
+    }
+
+    let doc = analyze_document(doc, morph, &dict).await?;
+    let segs = doc.info::<BTreeMap<usize, Segment>>()
+        .ok_or_else(|| anyhow!("document analysis missing"))?;
+    let mut segments = vec![];
+    for (start, seg) in segs.iter() {
+        segments.push(ParsedSegment {
+            range: seg.range.clone(),
+            text: seg.text.clone(),
+            words: resolve_stati(&dict, seg.words.clone()).await?,
+            parsed: resolve_stati(&dict, parsed.remove(start).unwrap_or_default()).await?,
+        });
+    }
+    let mut phrases = vec![];
+    if let Some(Phrases(found)) = doc.info() {
+        for phrase in found.values() {
+            phrases.push(ParsedSegment {
+                range: phrase.range.clone(),
+                text: phrase.text.clone(),
+                words: resolve_stati(&dict, phrase.words.clone()).await?,
+                parsed: vec![],
+            });
+        }
+    }
+
+    Ok(Json(ParseResponse { language: lang.to_string(), segments, phrases }))
+}
+
+async fn resolve_stati(dict: &Dictionary, mut words: Vec<Word>) -> Result<Vec<Word>> {
+    for word in words.iter_mut() {
+        word.resolved_status = Some(dict.resolve_status(word).await?);
+    }
+    Ok(words)
+}
+
+async fn get_parse(
+    State(ctx): State<Arc<Context>>,
+    Query(req): Query<LanguageRequest>,
+) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(req.lang.as_deref())?;
+    ctx.templates.lock().await.full_reload()?;
+    let mut tera = tera::Context::new();
+    tera.insert("language", lang);
+    tera.insert("languages", &ctx.morphs.languages().sorted().collect_vec());
+    Ok(Html(ctx.templates.lock().await.render("parse.html", &tera)?))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct BookReadRequest {
+    /// Whether to mark the words of the book that are still unknown as known.
+    #[serde(default)]
+    mark_known: bool,
+    /// The status to give those words (well known by default).
+    status: Option<WordStatus>,
+}
+
+/// Marks the book as read and, if requested, all its unknown words (or
+/// rather, their roots) as known. Returns the IDs of the words marked known.
 async fn post_book_read(
     State(ctx): State<Arc<Context>>,
     Path(id): Path<i64>,
+    Query(req): Query<BookReadRequest>,
 ) -> Result<impl IntoResponse> {
-    ctx.books.mark_book_read(id).await
+    let mut ids = vec![];
+    if req.mark_known {
+        let status = req.status.unwrap_or(WordStatus::WellKnown);
+        check(status != WordStatus::Unknown, "status must not be unknown")?;
+        let book = ctx.books.find_book_by_id(id).await?;
+        let (lang, _) = ctx.config.language(book.language.as_deref())?;
+        let dict = ctx.dict.for_language(lang);
+        let parser: Box<dyn doc::Parser> = match book.content_type.as_str() {
+            "text/plain" => Box::new(PlainTextParser),
+            "text/vtt" => Box::new(VttParser),
+            "text/markdown" => Box::new(MarkdownParser),
+            t => return bad_req(format!("invalid book content type: {t}").as_str()),
+        };
+        let doc = parser.parse_document(&book.content)?;
+        let doc = ctx.morphs.analyze_book(book.id, book.language.as_deref(), doc, &dict).await?;
+        let segs = doc.info::<BTreeMap<usize, Segment>>()
+            .ok_or_else(|| anyhow!("document analysis missing"))?;
+        let mut words = vec![];
+        for seg in segs.values() {
+            let (_, optimistic_rating) = dict.resolve_stati(seg.words.iter()).await?;
+            if optimistic_rating != WordStatus::Unknown {
+                continue;
+            }
+            if seg.words.is_empty() {
+                // As when adding a word from an unknown segment in the reader.
+                words.push(Word { text: seg.text.clone(), ..EMPTY_WORD });
+            } else {
+                words.extend(seg.words.iter().cloned());
+            }
+        }
+        let roots = dict.unknown_roots(words).await?;
+        ids = dict.set_words_status(roots, status, Some(lang)).await?;
+    }
+    ctx.books.mark_book_read(id).await?;
+    Ok(Json(ids))
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -1014,6 +1774,7 @@ async fn get_video(
 #[derive(Clone, Debug, Deserialize)]
 struct GetImportVideoRequest {
     url: Option<String>,
+    lang: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -1044,6 +1805,7 @@ async fn get_import_video(
     let Some(url) = req.url else {
         return bad_req("url required");
     };
+    let (lang_code, _) = ctx.config.language(req.lang.as_deref())?;
     let video = YoutubeDl::new(&url)
         .socket_timeout("15")
         .run_async().await?
@@ -1074,9 +1836,9 @@ async fn get_import_video(
         .map(|(k, v)| (k, v, true));
 
     for (lang, list, auto) in subtitles_iter.chain(auto_captions_iter) {
-        let prefix = format!("{}-", ctx.config.lang);
-        let prefix2 = format!("{}_", ctx.config.lang);
-        if lang != ctx.config.lang && !lang.starts_with(&prefix) && !lang.starts_with(&prefix2) {
+        let prefix = format!("{lang_code}-");
+        let prefix2 = format!("{lang_code}_");
+        if lang != lang_code && !lang.starts_with(&prefix) && !lang.starts_with(&prefix2) {
             continue;
         }
         for st in list {
@@ -1098,7 +1860,7 @@ async fn get_import_video(
     for plugin in ctx.config.import_plugins.iter() {
         subtitles.push(ImportVideoOptionsSubtitles {
             auto: false,
-            lang: ctx.config.lang.clone(),
+            lang: lang_code.to_string(),
             name: plugin.name.clone(),
             url: format!("plugin:{}", plugin.name),
         });
@@ -1111,6 +1873,8 @@ async fn get_import_video(
     tera.insert("tags", &tags);
     tera.insert("published", &video.timestamp);
     tera.insert("duration", &video.duration);
+    tera.insert("language", lang_code);
+    tera.insert("languages", &ctx.config.languages);
     Ok(Html(ctx.templates.lock().await.render("import_video.html", &tera)?))
 }
 
@@ -1178,6 +1942,7 @@ struct PostImportVideoRequest {
     tags: String,
     published: Option<i64>,
     duration: Option<f64>,
+    language: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -1189,6 +1954,8 @@ async fn post_import_video(
     State(ctx): State<Arc<Context>>,
     Form(req): Form<PostImportVideoRequest>,
 ) -> Result<impl IntoResponse> {
+    let (lang, _) = ctx.config.language(req.language.as_deref())?;
+    let language = Some(lang.to_string());
     let tags: Vec<ImportVideoTag> = serde_json::from_str(&req.tags)?;
     let tags = tags.into_iter().map(|t| t.value).collect_vec();
     let content = if req.subtitles == "manual" {
@@ -1210,7 +1977,7 @@ Language: {}
 00:00:00.000 --> {:0>2}:{:0>2}:{:0>2}.{:0>3}
 .
 "#,
-            ctx.config.lang, hh, mm, ss, ms)
+            lang, hh, mm, ss, ms)
     } else if let Some(plugin_name) = req.subtitles.strip_prefix("plugin:") {
         let plugins = ctx.config.import_plugins.clone();
         for plugin in plugins.into_iter() {
@@ -1318,6 +2085,7 @@ Language: {}
                     audio_file: None,
                     url: Some(url.clone()),
                     published: req.published.map(|t| Utc.timestamp_opt(t, 0).unwrap()),
+                    language,
                     tags,
                 }).await;
                 match result {
@@ -1343,6 +2111,7 @@ Language: {}
         audio_file: None,
         url: Some(req.url),
         published: req.published.map(|t| Utc.timestamp_opt(t, 0).unwrap()),
+        language,
         tags,
     }).await?;
     Ok(Redirect::to(&format!("/read/{id}")))
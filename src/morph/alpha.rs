@@ -1,29 +1,278 @@
+use std::{ops::Range, path::PathBuf};
+
 use axum::async_trait;
+use figment::value::magic::RelativePathBuf;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
 
-use super::{Parser, Segment};
-use crate::Result;
+use super::{maybe_string_to_list, Parser, Segment};
+use crate::{dict::{Dictionary, Word, EMPTY_WORD}, Result};
 
-pub struct AlphabeticParser;
+const DEFAULT_MIN_STEM: usize = 2;
 
-#[async_trait]
-impl Parser for AlphabeticParser {
-    async fn parse(&self, text: &str) -> Result<Vec<Segment>> {
-        let mut segs = vec![];
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlphabeticTokenizer {
+    /// Words are runs of alphabetic characters.
+    #[default]
+    Simple,
+    /// Words are determined by Unicode (UAX#29) word boundaries.
+    Unicode,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AlphabeticConfig {
+    rules: Option<RelativePathBuf>,
+    #[serde(default)]
+    tokenizer: AlphabeticTokenizer,
+    /// Additional characters that join two words into one when they appear
+    /// between them (e.g., "-" for "e-mail").
+    #[serde(default)]
+    word_chars: String,
+    /// Elided prefixes to split off from the start of a word (e.g., "l'" for
+    /// "l'homme"). Matching ignores case and the style of apostrophe.
+    #[serde(default)]
+    elisions: Vec<String>,
+    /// Whether to propose the lowercase form of a word as its parent.
+    #[serde(default)]
+    case_fold: bool,
+}
+
+impl AlphabeticConfig {
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.rules.iter().map(|p| p.relative()).collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct RuleRow {
+    suffix: String,
+    replacement: Option<String>,
+    tags: Option<String>,
+    min_stem: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    suffix: String,
+    replacement: String,
+    tags: Vec<String>,
+    min_stem: usize,
+}
+
+impl From<RuleRow> for Rule {
+    fn from(row: RuleRow) -> Self {
+        Self {
+            suffix: row.suffix,
+            replacement: row.replacement.unwrap_or_default(),
+            tags: maybe_string_to_list(row.tags),
+            min_stem: row.min_stem.unwrap_or(DEFAULT_MIN_STEM),
+        }
+    }
+}
+
+impl Rule {
+    /// Applies this rule to `text`, returning the candidate lemma, if the rule
+    /// matches.
+    fn apply(&self, text: &str) -> Option<String> {
+        let stem = text.strip_suffix(&self.suffix)?;
+        if stem.chars().count() < self.min_stem {
+            return None;
+        }
+        let lemma = format!("{stem}{}", self.replacement);
+        (lemma != text).then_some(lemma)
+    }
+}
+
+struct Candidate {
+    lemma: String,
+    tags: Vec<String>,
+    debug: Vec<String>,
+}
+
+fn normalize_elision(s: &str) -> String {
+    s.to_lowercase().replace('\u{2019}', "'")
+}
+
+pub struct AlphabeticParser {
+    rules: Vec<Rule>,
+    tokenizer: AlphabeticTokenizer,
+    word_chars: Vec<char>,
+    elisions: Vec<String>,
+    case_fold: bool,
+    lit_dict: Dictionary,
+}
+
+impl AlphabeticParser {
+    pub fn load(config: &AlphabeticConfig, lit_dict: Dictionary) -> Result<Self> {
+        let mut rules = vec![];
+        if let Some(ref path) = config.rules {
+            let mut rdr = csv::ReaderBuilder::new()
+                .flexible(true)
+                .comment(Some(b'#'))
+                .from_path(path.relative())?;
+            for rule in rdr.deserialize::<RuleRow>() {
+                let rule: Rule = rule?.into();
+                if rules.iter().any(|r: &Rule| r.suffix == rule.suffix && r.replacement == rule.replacement) {
+                    println!("WARNING: duplicate inflection rule: -{} -> -{}", rule.suffix, rule.replacement);
+                }
+                rules.push(rule);
+            }
+        }
+        Ok(AlphabeticParser {
+            rules,
+            tokenizer: config.tokenizer,
+            word_chars: config.word_chars.chars().collect(),
+            elisions: config.elisions.iter().map(|e| normalize_elision(e)).collect(),
+            case_fold: config.case_fold,
+            lit_dict,
+        })
+    }
+
+    fn tokenize_simple(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
         let mut start = None;
-        for (i, ch) in text.char_indices().chain([(text.len(), '.')]) {
-            match (start, ch.is_alphabetic()) {
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, ch)) = chars.next() {
+            let joins = start.is_some()
+                && self.word_chars.contains(&ch)
+                && chars.peek().is_some_and(|(_, next)| next.is_alphabetic());
+            match (start, ch.is_alphabetic() || joins) {
                 (None, true) => start = Some(i),
                 (Some(j), false) => {
                     start = None;
-                    segs.push(Segment {
-                        range: j..i,
-                        text: text[j..i].to_string(),
-                        words: vec![],
-                    });
+                    ranges.push(j..i);
                 }
                 _ => (),
             }
         }
+        if let Some(j) = start {
+            ranges.push(j..text.len());
+        }
+        ranges
+    }
+
+    fn tokenize_unicode(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        let mut joiner: Option<Range<usize>> = None;
+        for (i, word) in text.split_word_bound_indices() {
+            let range = i..(i + word.len());
+            if word.chars().any(char::is_alphanumeric) {
+                match (joiner.take(), ranges.last_mut()) {
+                    (Some(j), Some(last)) if last.end == j.start && j.end == range.start => {
+                        last.end = range.end;
+                    }
+                    _ => ranges.push(range),
+                }
+                continue;
+            }
+            let mut chars = word.chars();
+            joiner = match (chars.next(), chars.next()) {
+                (Some(ch), None) if self.word_chars.contains(&ch) => Some(range),
+                _ => None,
+            };
+        }
+        ranges.retain(|r| text[r.clone()].chars().any(char::is_alphabetic));
+        ranges
+    }
+
+    /// Splits an elided prefix (e.g., "l'" in "l'homme") off of the start of
+    /// the word in the given range, if there is one.
+    fn split_elision(&self, text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+        let word = &text[range.clone()];
+        for elision in self.elisions.iter() {
+            let n = elision.chars().count();
+            let Some((split, _)) = word.char_indices().nth(n) else {
+                continue;
+            };
+            if normalize_elision(&word[..split]) == *elision {
+                let split = range.start + split;
+                return vec![range.start..split, split..range.end];
+            }
+        }
+        vec![range]
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+        let ranges = match self.tokenizer {
+            AlphabeticTokenizer::Simple => self.tokenize_simple(text),
+            AlphabeticTokenizer::Unicode => self.tokenize_unicode(text),
+        };
+        ranges.into_iter()
+            .flat_map(|r| self.split_elision(text, r))
+            .collect()
+    }
+
+    /// Proposes words for `text` with candidate lemmas as parents, based on
+    /// the configured inflection rules (and case folding, if enabled). If any
+    /// of the candidate lemmas are already in the dictionary, only those are
+    /// proposed.
+    async fn inflections(&self, text: &str) -> Result<Vec<Word>> {
+        let mut candidates: Vec<Candidate> = vec![];
+        let folded = if self.case_fold { text.to_lowercase() } else { text.to_string() };
+        if folded != text {
+            candidates.push(Candidate {
+                lemma: folded.clone(),
+                tags: vec![],
+                debug: vec!["case folded".to_string()],
+            });
+        }
+        for rule in self.rules.iter() {
+            let Some(lemma) = rule.apply(&folded) else {
+                continue;
+            };
+            let debug = format!("-{} -> -{}", rule.suffix, rule.replacement);
+            match candidates.iter_mut().find(|c| c.lemma == lemma) {
+                Some(c) => {
+                    for tag in rule.tags.iter() {
+                        if !c.tags.contains(tag) {
+                            c.tags.push(tag.clone());
+                        }
+                    }
+                    c.debug.push(debug);
+                }
+                None => candidates.push(Candidate {
+                    lemma,
+                    tags: rule.tags.clone(),
+                    debug: vec![debug],
+                }),
+            }
+        }
+
+        let mut known = vec![];
+        for c in candidates.iter() {
+            known.push(self.lit_dict.word_exists(&c.lemma).await?);
+        }
+        let any_known = known.iter().any(|k| *k);
+
+        Ok(candidates.into_iter()
+            .zip(known)
+            .filter(|(_, known)| *known || !any_known)
+            .map(|(c, _)| Word {
+                text: text.to_string(),
+                parents: vec![c.lemma],
+                tags: c.tags,
+                debug: Some(c.debug.join("\n")),
+                inherit: true,
+                ..EMPTY_WORD
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Parser for AlphabeticParser {
+    async fn parse(&self, text: &str) -> Result<Vec<Segment>> {
+        let mut segs = vec![];
+        for range in self.tokenize(text) {
+            let word = &text[range.clone()];
+            segs.push(Segment {
+                range,
+                text: word.to_string(),
+                words: self.inflections(word).await?,
+                sentence_end: false,
+            });
+        }
         Ok(segs)
     }
 }
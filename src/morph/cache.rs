@@ -0,0 +1,181 @@
+use std::{collections::BTreeMap, ops::Range, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite};
+
+use crate::{dict::{Dictionary, Word, WordStatus, EMPTY_WORD}, Result};
+
+use super::{MorphConfig, Segment};
+
+/// Version of the segmentation format and parser behavior. Bump this whenever
+/// a change to a parser would change its output for the same input, so that
+/// previously cached segmentations are discarded.
+const SEGMENTATION_VERSION: u32 = 7;
+
+#[derive(Serialize, Deserialize)]
+struct CachedWord {
+    text: String,
+    status: Option<WordStatus>,
+    pronunciation: Option<String>,
+    translation: String,
+    tags: Vec<String>,
+    parents: Vec<String>,
+    debug: Option<String>,
+    #[serde(default)]
+    hanja: Option<String>,
+    #[serde(default)]
+    grammar: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedSegment {
+    range: Range<usize>,
+    text: String,
+    words: Vec<CachedWord>,
+    #[serde(default)]
+    sentence_end: bool,
+}
+
+impl From<&Segment> for CachedSegment {
+    fn from(seg: &Segment) -> Self {
+        let words = seg.words.iter().map(|w| CachedWord {
+            text: w.text.clone(),
+            status: w.status,
+            pronunciation: w.pronunciation.clone(),
+            translation: w.translation.clone(),
+            tags: w.tags.clone(),
+            parents: w.parents.clone(),
+            debug: w.debug.clone(),
+            hanja: w.hanja.clone(),
+            grammar: w.grammar.clone(),
+        }).collect();
+        Self { range: seg.range.clone(), text: seg.text.clone(), words, sentence_end: seg.sentence_end }
+    }
+}
+
+impl From<CachedSegment> for Segment {
+    fn from(seg: CachedSegment) -> Self {
+        let words = seg.words.into_iter().map(|w| Word {
+            text: w.text,
+            status: w.status,
+            inherit: w.status.is_none(),
+            pronunciation: w.pronunciation,
+            translation: w.translation,
+            tags: w.tags,
+            parents: w.parents,
+            debug: w.debug,
+            hanja: w.hanja,
+            grammar: w.grammar,
+            ..EMPTY_WORD
+        }).collect();
+        Self { range: seg.range, text: seg.text, words, sentence_end: seg.sentence_end }
+    }
+}
+
+/// Returns a version string identifying the parser that would be loaded from
+/// the given configuration, including the files it loads (by size and
+/// modification time), except for those it reloads when they change (see
+/// `MorphConfig::reloadable_files`), whose version is given by `files_version`.
+pub fn parser_version(config: &MorphConfig) -> Result<String> {
+    let reloadable = config.reloadable_files();
+    let mut hash = Sha256::new();
+    hash.update(SEGMENTATION_VERSION.to_le_bytes());
+    hash.update(serde_json::to_vec(config)?);
+    for path in config.files().iter().filter(|path| !reloadable.contains(path)) {
+        hash_file(&mut hash, path)?;
+    }
+    Ok(format!("{:x}", hash.finalize()))
+}
+
+/// Returns a version string identifying the given files, by size and
+/// modification time.
+pub fn files_version(paths: &[PathBuf]) -> Result<String> {
+    let mut hash = Sha256::new();
+    for path in paths {
+        hash_file(&mut hash, path)?;
+    }
+    Ok(format!("{:x}", hash.finalize()))
+}
+
+fn hash_file(hash: &mut Sha256, path: &Path) -> Result<()> {
+    let md = std::fs::metadata(path)?;
+    hash.update(path.to_string_lossy().as_bytes());
+    hash.update(md.len().to_le_bytes());
+    if let Ok(modified) = md.modified() {
+        hash.update(format!("{modified:?}"));
+    }
+    Ok(())
+}
+
+/// Persistent cache of the parser output for each book, so that books don't
+/// need to be re-tokenized every time they're analyzed.
+///
+/// Only the parser output is cached. Dictionary words are looked up again each
+/// time the segmentation is loaded. Since parsers consult the dictionary (e.g.,
+/// to choose between candidate roots), each segmentation is stored with the
+/// texts the parser checked (see `dict::track_checked_words`), and is only
+/// used while none of them has been added to or removed from the dictionary.
+#[derive(Clone)]
+pub struct SegmentCache {
+    db: Pool<Sqlite>,
+}
+
+impl SegmentCache {
+    pub fn new(db: Pool<Sqlite>) -> Self {
+        Self { db }
+    }
+
+    /// Returns the cache key for parsing the given spans of `text` with the
+    /// parser identified by `version`.
+    pub fn key(version: &str, text: &str, spans: &[Range<usize>]) -> String {
+        let mut hash = Sha256::new();
+        hash.update(text.as_bytes());
+        for span in spans {
+            hash.update(span.start.to_le_bytes());
+            hash.update(span.end.to_le_bytes());
+        }
+        format!("{version}:{:x}", hash.finalize())
+    }
+
+    /// Returns the cached segmentation of the book, unless the key differs or
+    /// one of the texts the parser checked has since been added to or removed
+    /// from `dict`, which must be limited to the language of the book.
+    pub async fn get(&self, book_id: i64, key: &str, dict: &Dictionary) -> Result<Option<Vec<Segment>>> {
+        let rec: Option<(String, String)> = sqlx::query_as("SELECT segments, checked FROM book_segmentation WHERE book_id = ? AND key = ?")
+            .bind(book_id)
+            .bind(key)
+            .fetch_optional(&self.db)
+            .await?;
+        let Some((json, checked)) = rec else {
+            return Ok(None);
+        };
+        let checked: BTreeMap<String, bool> = serde_json::from_str(&checked)?;
+        for (text, existed) in checked {
+            if dict.word_exists(&text).await? != existed {
+                return Ok(None);
+            }
+        }
+        let segs: Vec<CachedSegment> = serde_json::from_str(&json)?;
+        Ok(Some(segs.into_iter().map(|seg| seg.into()).collect()))
+    }
+
+    /// Saves the segmentation of the book, along with the texts the parser
+    /// checked and whether they existed.
+    pub async fn put(&self, book_id: i64, key: &str, segs: &[Segment], checked: &BTreeMap<String, bool>) -> Result<()> {
+        let segs: Vec<CachedSegment> = segs.iter().map(|seg| seg.into()).collect();
+        let json = serde_json::to_string(&segs)?;
+        sqlx::query("
+            INSERT INTO book_segmentation (book_id, key, segments, checked)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (book_id) DO UPDATE SET key = excluded.key, segments = excluded.segments, checked = excluded.checked
+            ")
+            .bind(book_id)
+            .bind(key)
+            .bind(json)
+            .bind(serde_json::to_string(checked)?)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use std::{collections::HashMap, fs::File, io::{BufRead, BufReader}, path::PathBuf};
+
+use anyhow::anyhow;
+use axum::async_trait;
+use figment::value::magic::RelativePathBuf;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{dict::{Word, WordStatus, EMPTY_WORD}, Result};
+
+use super::{Parser, Segment};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChineseScript {
+    Simplified,
+    Traditional,
+    #[default]
+    Both,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChineseConfig {
+    dictionary: RelativePathBuf,
+    #[serde(default)]
+    script: ChineseScript,
+}
+
+impl ChineseConfig {
+    pub fn files(&self) -> Vec<PathBuf> {
+        vec![self.dictionary.relative()]
+    }
+}
+
+pub(crate) fn is_han(c: char) -> bool {
+    matches!(c,
+        '\u{3007}' |
+        '\u{3400}'..='\u{4dbf}' |
+        '\u{4e00}'..='\u{9fff}' |
+        '\u{f900}'..='\u{faff}' |
+        '\u{20000}'..='\u{2a6df}' |
+        '\u{2a700}'..='\u{2ebef}')
+}
+
+const TONE_MARKS: [(char, [char; 4]); 6] = [
+    ('a', ['ā', 'á', 'ǎ', 'à']),
+    ('e', ['ē', 'é', 'ě', 'è']),
+    ('i', ['ī', 'í', 'ǐ', 'ì']),
+    ('o', ['ō', 'ó', 'ǒ', 'ò']),
+    ('u', ['ū', 'ú', 'ǔ', 'ù']),
+    ('ü', ['ǖ', 'ǘ', 'ǚ', 'ǜ']),
+];
+
+/// Converts a single CC-CEDICT pinyin syllable (e.g., "lu:3") to use tone
+/// marks (e.g., "lǚ"). Syllables that aren't in numbered form are returned
+/// as-is.
+fn syllable_to_tone_marks(syllable: &str) -> String {
+    let syllable = syllable.replace("u:", "ü").replace("U:", "Ü");
+    let Some(tone) = syllable.chars().last().and_then(|c| c.to_digit(10)) else {
+        return syllable;
+    };
+    let base = &syllable[..syllable.len() - 1];
+    if !(1..=4).contains(&tone) {
+        return base.to_string();
+    }
+    let lower = base.to_lowercase();
+    let target = lower.find(['a', 'e'])
+        .or_else(|| lower.find("ou"))
+        .or_else(|| {
+            lower.char_indices()
+                .rev()
+                .find(|(_, c)| TONE_MARKS.iter().any(|(v, _)| v == c))
+                .map(|(i, _)| i)
+        });
+    let Some(target) = target else {
+        return base.to_string();
+    };
+    base.char_indices().map(|(i, c)| {
+        if i != target {
+            return c.to_string();
+        }
+        let lc = c.to_lowercase().next().unwrap_or(c);
+        let marked = TONE_MARKS.iter()
+            .find(|(v, _)| *v == lc)
+            .map(|(_, marks)| marks[tone as usize - 1])
+            .unwrap_or(c);
+        if c.is_uppercase() {
+            marked.to_uppercase().to_string()
+        } else {
+            marked.to_string()
+        }
+    }).collect()
+}
+
+fn pinyin_to_tone_marks(pinyin: &str) -> String {
+    pinyin.split_whitespace().map(syllable_to_tone_marks).join(" ")
+}
+
+pub struct ChineseParser {
+    words: HashMap<String, Vec<String>>,
+    max_word_chars: usize,
+}
+
+impl ChineseParser {
+    pub fn load(config: &ChineseConfig) -> Result<Self> {
+        let mut words: HashMap<String, Vec<String>> = HashMap::new();
+        let mut max_word_chars = 1;
+        let rdr = BufReader::new(File::open(config.dictionary.relative())?);
+        for line in rdr.lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            // Traditional Simplified [pin1 yin1] /definition 1/definition 2/
+            let Some((head, rest)) = line.split_once(" [") else {
+                return Err(anyhow!("invalid dictionary line: {line}").into());
+            };
+            let Some((pinyin, _)) = rest.split_once(']') else {
+                return Err(anyhow!("invalid dictionary line: {line}").into());
+            };
+            let Some((traditional, simplified)) = head.split_once(' ') else {
+                return Err(anyhow!("invalid dictionary line: {line}").into());
+            };
+            let pinyin = pinyin_to_tone_marks(pinyin);
+            let forms = match config.script {
+                ChineseScript::Simplified => vec![simplified],
+                ChineseScript::Traditional => vec![traditional],
+                ChineseScript::Both => vec![simplified, traditional],
+            };
+            for form in forms.into_iter().dedup() {
+                max_word_chars = max_word_chars.max(form.chars().count());
+                let readings = words.entry(form.to_string()).or_default();
+                if !readings.contains(&pinyin) {
+                    readings.push(pinyin.clone());
+                }
+            }
+        }
+        Ok(ChineseParser { words, max_word_chars })
+    }
+
+    /// Segments a run of Han characters, choosing the segmentation with the
+    /// fewest words (i.e., preferring longer dictionary entries). Characters
+    /// not covered by any dictionary entry become single-character words.
+    fn segment_han(&self, text: &str) -> Vec<usize> {
+        let bounds = text.char_indices().map(|(i, _)| i)
+            .chain([text.len()])
+            .collect_vec();
+        let n = bounds.len() - 1;
+        // best[i] = (number of words, start of last word) for text[..bounds[i]]
+        let mut best: Vec<Option<(usize, usize)>> = vec![None; n + 1];
+        best[0] = Some((0, 0));
+        for i in 0..n {
+            let Some((count, _)) = best[i] else {
+                continue;
+            };
+            for len in 1..=self.max_word_chars.min(n - i) {
+                let j = i + len;
+                if len > 1 && !self.words.contains_key(&text[bounds[i]..bounds[j]]) {
+                    continue;
+                }
+                if best[j].map(|(c, _)| count + 1 < c).unwrap_or(true) {
+                    best[j] = Some((count + 1, i));
+                }
+            }
+        }
+        let mut ends = vec![];
+        let mut j = n;
+        while j > 0 {
+            ends.push(bounds[j]);
+            j = best[j].map(|(_, i)| i).unwrap_or(j - 1);
+        }
+        ends.reverse();
+        ends
+    }
+
+    fn word(&self, text: &str) -> Word {
+        let pronunciation = self.words.get(text)
+            .map(|readings| readings.join(" / "));
+        Word {
+            text: text.to_string(),
+            pronunciation,
+            status: Some(WordStatus::Unknown),
+            ..EMPTY_WORD
+        }
+    }
+}
+
+#[async_trait]
+impl Parser for ChineseParser {
+    async fn parse(&self, text: &str) -> Result<Vec<Segment>> {
+        let mut segs = vec![];
+        let runs = text.char_indices()
+            .chunk_by(|(_, c)| if is_han(*c) { Some(true) } else if c.is_alphanumeric() { Some(false) } else { None });
+        for (kind, run) in &runs {
+            let Some(han) = kind else {
+                continue;
+            };
+            let run = run.collect_vec();
+            let start = run[0].0;
+            let (last_i, last_c) = *run.last().unwrap();
+            let end = last_i + last_c.len_utf8();
+            let ends = if han {
+                self.segment_han(&text[start..end]).into_iter().map(|e| start + e).collect_vec()
+            } else {
+                vec![end]
+            };
+            let mut pos = start;
+            for end in ends {
+                let word_text = &text[pos..end];
+                segs.push(Segment {
+                    range: pos..end,
+                    text: word_text.to_string(),
+                    words: if han { vec![self.word(word_text)] } else { vec![] },
+                    sentence_end: false,
+                });
+                pos = end;
+            }
+        }
+        Ok(segs)
+    }
+}
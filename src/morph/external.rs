@@ -0,0 +1,181 @@
+use std::{path::PathBuf, process::Stdio, time::Duration};
+
+use anyhow::anyhow;
+use axum::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::{io::{AsyncBufReadExt, AsyncWriteExt, BufReader}, process::{Child, ChildStdin, ChildStdout, Command}, sync::Mutex, time::timeout};
+
+use crate::{dict::{Word, WordStatus, EMPTY_WORD}, Result};
+
+use super::{Parser, Segment};
+
+/// Configuration for a parser that runs in a separate, long-running process,
+/// for analyzers that can't be linked into LIT (e.g., Python libraries).
+///
+/// The process reads one JSON request per line from stdin, of the form
+/// `{"text": "..."}`, and must write one JSON response per line to stdout, of
+/// the form:
+///
+/// ```json
+/// {"segments": [{"start": 0, "end": 5, "lemma": "...", "pos": "...", "tags": ["..."]}]}
+/// ```
+///
+/// `start` and `end` are character (not byte) offsets into the text, and
+/// `lemma`, `pos`, `tags`, `pronunciation`, `debug` and `sentence_end` (whether
+/// the segment ends a sentence) are optional. The
+/// response may instead be `{"error": "..."}`. Anything written to stderr is
+/// passed through to LIT's stderr. If the process doesn't respond within
+/// `timeout` seconds (30 by default), it is killed and a new one is started for
+/// the next text.
+///
+/// Note that cached segmentations are only discarded when this configuration
+/// changes, so change `args` (e.g., by passing the analyzer's version) when the
+/// analyzer's output changes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExternalConfig {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+impl ExternalConfig {
+    pub fn files(&self) -> Vec<PathBuf> {
+        vec![]
+    }
+}
+
+#[derive(Serialize)]
+struct ExternalRequest<'a> {
+    text: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ExternalSegment {
+    start: usize,
+    end: usize,
+    lemma: Option<String>,
+    pos: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    pronunciation: Option<String>,
+    debug: Option<String>,
+    #[serde(default)]
+    sentence_end: bool,
+}
+
+#[derive(Deserialize)]
+struct ExternalResponse {
+    #[serde(default)]
+    segments: Vec<ExternalSegment>,
+    error: Option<String>,
+}
+
+struct Process {
+    // Kept so that the process is killed when the parser is dropped.
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+pub struct ExternalParser {
+    config: ExternalConfig,
+    process: Mutex<Option<Process>>,
+}
+
+impl ExternalParser {
+    /// Creates the parser. The process isn't started until the first text is
+    /// parsed.
+    pub fn load(config: &ExternalConfig) -> Result<Self> {
+        Ok(ExternalParser { config: config.clone(), process: Mutex::new(None) })
+    }
+
+    fn spawn(&self) -> Result<Process> {
+        let mut child = Command::new(&self.config.command)
+            .args(&self.config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("failed to start {}: {e}", self.config.command))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("no stdin for {}", self.config.command))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("no stdout for {}", self.config.command))?;
+        Ok(Process { _child: child, stdin, stdout: BufReader::new(stdout) })
+    }
+
+    /// Sends the text to the process and reads its response, starting the
+    /// process if it isn't running. If the exchange fails, times out or is
+    /// cancelled, the process is killed so that a fresh one is started for the
+    /// next text.
+    async fn request(&self, text: &str) -> Result<ExternalResponse> {
+        let mut process = self.process.lock().await;
+        let mut p = match process.take() {
+            Some(p) => p,
+            None => self.spawn()?,
+        };
+        let resp = timeout(Duration::from_secs(self.config.timeout), Self::exchange(&mut p, text)).await
+            .map_err(|_| anyhow!("external parser did not respond within {} seconds", self.config.timeout))??;
+        *process = Some(p);
+        Ok(resp)
+    }
+
+    async fn exchange(p: &mut Process, text: &str) -> Result<ExternalResponse> {
+        let mut line = serde_json::to_string(&ExternalRequest { text })?;
+        line.push('\n');
+        p.stdin.write_all(line.as_bytes()).await?;
+        p.stdin.flush().await?;
+        let mut resp = String::new();
+        if p.stdout.read_line(&mut resp).await? == 0 {
+            return Err(anyhow!("external parser exited").into());
+        }
+        let resp = serde_json::from_str(&resp)
+            .map_err(|e| anyhow!("invalid response from external parser: {e}"))?;
+        Ok(resp)
+    }
+}
+
+#[async_trait]
+impl Parser for ExternalParser {
+    async fn parse(&self, text: &str) -> Result<Vec<Segment>> {
+        let resp = self.request(text).await?;
+        if let Some(err) = resp.error {
+            return Err(anyhow!("external parser failed: {err}").into());
+        }
+        // Character offset => byte offset.
+        let offsets: Vec<usize> = text.char_indices()
+            .map(|(i, _)| i)
+            .chain([text.len()])
+            .collect();
+        let mut segs = vec![];
+        for seg in resp.segments {
+            if seg.start >= seg.end || seg.end >= offsets.len() {
+                return Err(anyhow!("invalid segment from external parser: {}..{}", seg.start, seg.end).into());
+            }
+            let range = offsets[seg.start]..offsets[seg.end];
+            let surface = text[range.clone()].to_string();
+            let parents = seg.lemma.into_iter()
+                .filter(|lemma| *lemma != surface)
+                .collect();
+            let tags = seg.pos.into_iter()
+                .chain(seg.tags)
+                .collect();
+            let word = Word {
+                text: surface.clone(),
+                status: Some(WordStatus::Unknown),
+                pronunciation: seg.pronunciation,
+                tags,
+                parents,
+                debug: seg.debug,
+                ..EMPTY_WORD
+            };
+            segs.push(Segment { range, text: surface, words: vec![word], sentence_end: seg.sentence_end });
+        }
+        Ok(segs)
+    }
+}
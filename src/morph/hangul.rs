@@ -0,0 +1,333 @@
+//! Pronunciation of Hangul text according to the standard sound-change rules
+//! (표준 발음법), and Revised Romanization of the resulting pronunciation.
+//!
+//! Rules are applied only within runs of Hangul syllables. Rules which depend
+//! on morphology (e.g., ㄴ-insertion in compounds like 솜이불 [솜니불]) are not
+//! applied.
+
+const INITIALS: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ',
+    'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ', 'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+const VOWELS: [char; 21] = [
+    'ㅏ', 'ㅐ', 'ㅑ', 'ㅒ', 'ㅓ', 'ㅔ', 'ㅕ', 'ㅖ', 'ㅗ', 'ㅘ', 'ㅙ',
+    'ㅚ', 'ㅛ', 'ㅜ', 'ㅝ', 'ㅞ', 'ㅟ', 'ㅠ', 'ㅡ', 'ㅢ', 'ㅣ',
+];
+
+const FINALS: [Option<char>; 28] = [
+    None, Some('ㄱ'), Some('ㄲ'), Some('ㄳ'), Some('ㄴ'), Some('ㄵ'), Some('ㄶ'),
+    Some('ㄷ'), Some('ㄹ'), Some('ㄺ'), Some('ㄻ'), Some('ㄼ'), Some('ㄽ'),
+    Some('ㄾ'), Some('ㄿ'), Some('ㅀ'), Some('ㅁ'), Some('ㅂ'), Some('ㅄ'),
+    Some('ㅅ'), Some('ㅆ'), Some('ㅇ'), Some('ㅈ'), Some('ㅊ'), Some('ㅋ'),
+    Some('ㅌ'), Some('ㅍ'), Some('ㅎ'),
+];
+
+const ROMAN_INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s",
+    "ss", "", "j", "jj", "ch", "k", "t", "p", "h",
+];
+
+const ROMAN_VOWELS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae",
+    "oe", "yo", "u", "wo", "we", "wi", "yu", "eu", "ui", "i",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Syllable {
+    initial: char,
+    vowel: char,
+    coda: Option<char>,
+}
+
+impl Syllable {
+    fn decompose(c: char) -> Option<Self> {
+        let n = (c as u32).checked_sub(0xac00)? as usize;
+        if n >= INITIALS.len() * VOWELS.len() * FINALS.len() {
+            return None;
+        }
+        Some(Syllable {
+            initial: INITIALS[n / (VOWELS.len() * FINALS.len())],
+            vowel: VOWELS[n / FINALS.len() % VOWELS.len()],
+            coda: FINALS[n % FINALS.len()],
+        })
+    }
+
+    fn compose(&self) -> char {
+        let l = INITIALS.iter().position(|c| *c == self.initial).unwrap_or(11);
+        let v = VOWELS.iter().position(|c| *c == self.vowel).unwrap_or(0);
+        let t = FINALS.iter().position(|c| *c == self.coda).unwrap_or(0);
+        let n = (l * VOWELS.len() + v) * FINALS.len() + t;
+        char::from_u32(0xac00 + n as u32).unwrap_or('\u{fffd}')
+    }
+
+    fn romanize(&self, prev_coda: Option<char>) -> String {
+        let initial = match (prev_coda, self.initial) {
+            (Some('ㄹ'), 'ㄹ') => "l",
+            (_, c) => INITIALS.iter().position(|i| *i == c).map(|i| ROMAN_INITIALS[i]).unwrap_or(""),
+        };
+        let vowel = VOWELS.iter().position(|v| *v == self.vowel).map(|i| ROMAN_VOWELS[i]).unwrap_or("");
+        let coda = match self.coda {
+            Some('ㄱ') => "k",
+            Some('ㄴ') => "n",
+            Some('ㄷ') => "t",
+            Some('ㄹ') => "l",
+            Some('ㅁ') => "m",
+            Some('ㅂ') => "p",
+            Some('ㅇ') => "ng",
+            _ => "",
+        };
+        format!("{initial}{vowel}{coda}")
+    }
+}
+
+enum Piece {
+    Hangul(Syllable),
+    Other(char),
+}
+
+/// Splits a final consonant into the part that stays in place and the last
+/// consonant, which may be carried over to the next syllable (e.g., ㄺ into
+/// ㄹ and ㄱ). Double consonants (ㄲ, ㅆ) are not split.
+fn split_coda(coda: char) -> (Option<char>, char) {
+    match coda {
+        'ㄳ' => (Some('ㄱ'), 'ㅅ'),
+        'ㄵ' => (Some('ㄴ'), 'ㅈ'),
+        'ㄶ' => (Some('ㄴ'), 'ㅎ'),
+        'ㄺ' => (Some('ㄹ'), 'ㄱ'),
+        'ㄻ' => (Some('ㄹ'), 'ㅁ'),
+        'ㄼ' => (Some('ㄹ'), 'ㅂ'),
+        'ㄽ' => (Some('ㄹ'), 'ㅅ'),
+        'ㄾ' => (Some('ㄹ'), 'ㅌ'),
+        'ㄿ' => (Some('ㄹ'), 'ㅍ'),
+        'ㅀ' => (Some('ㄹ'), 'ㅎ'),
+        'ㅄ' => (Some('ㅂ'), 'ㅅ'),
+        c => (None, c),
+    }
+}
+
+fn tensify(initial: char) -> Option<char> {
+    match initial {
+        'ㄱ' => Some('ㄲ'),
+        'ㄷ' => Some('ㄸ'),
+        'ㅂ' => Some('ㅃ'),
+        'ㅅ' => Some('ㅆ'),
+        'ㅈ' => Some('ㅉ'),
+        _ => None,
+    }
+}
+
+/// Reduces a final consonant to one of the seven that can be pronounced at the
+/// end of a syllable (음절의 끝소리 규칙).
+fn neutralize(syllable: &Syllable, coda: char, next_initial: Option<char>) -> char {
+    match coda {
+        'ㄱ' | 'ㄲ' | 'ㅋ' | 'ㄳ' => 'ㄱ',
+        'ㄺ' if next_initial == Some('ㄱ') => 'ㄹ',
+        'ㄺ' => 'ㄱ',
+        'ㄴ' | 'ㄵ' | 'ㄶ' => 'ㄴ',
+        'ㄷ' | 'ㅅ' | 'ㅆ' | 'ㅈ' | 'ㅊ' | 'ㅌ' | 'ㅎ' => 'ㄷ',
+        // 밟- is an exception to the usual reduction of ㄼ to ㄹ.
+        'ㄼ' if syllable.initial == 'ㅂ' && syllable.vowel == 'ㅏ' => 'ㅂ',
+        'ㄹ' | 'ㄼ' | 'ㄽ' | 'ㄾ' | 'ㅀ' => 'ㄹ',
+        'ㅁ' | 'ㄻ' => 'ㅁ',
+        'ㅂ' | 'ㅍ' | 'ㅄ' | 'ㄿ' => 'ㅂ',
+        c => c,
+    }
+}
+
+/// Calls `f` with each pair of adjacent Hangul syllables, along with the index
+/// of the first syllable of the pair.
+fn for_each_pair(pieces: &mut [Piece], mut f: impl FnMut(usize, &mut Syllable, &mut Syllable)) {
+    for i in 1..pieces.len() {
+        let (head, tail) = pieces.split_at_mut(i);
+        if let (Some(Piece::Hangul(prev)), Piece::Hangul(next)) = (head.last_mut(), &mut tail[0]) {
+            f(i - 1, prev, next);
+        }
+    }
+}
+
+/// ㅎ is either merged with an adjacent plain consonant into an aspirated
+/// consonant (좋다 [조타], 밝히다 [발키다]), or dropped (좋아 [조아]).
+fn apply_h_rules(pieces: &mut [Piece]) {
+    for_each_pair(pieces, |_, prev, next| {
+        let Some(coda) = prev.coda else {
+            return;
+        };
+        let (rest, last) = split_coda(coda);
+        if last == 'ㅎ' {
+            match next.initial {
+                'ㄱ' => next.initial = 'ㅋ',
+                'ㄷ' => next.initial = 'ㅌ',
+                'ㅈ' => next.initial = 'ㅊ',
+                'ㅅ' => next.initial = 'ㅆ',
+                'ㄴ' => {
+                    prev.coda = Some(rest.unwrap_or('ㄴ'));
+                    return;
+                },
+                'ㅇ' => (),
+                _ => return,
+            }
+            prev.coda = rest;
+        } else if next.initial == 'ㅎ' {
+            // For ㄳ and ㅄ, it's the first consonant that aspirates.
+            let (rest, last) = match (rest, last) {
+                (Some(c @ ('ㄱ' | 'ㅂ')), 'ㅅ') => (None, c),
+                _ => (rest, last),
+            };
+            next.initial = match last {
+                'ㄱ' => 'ㅋ',
+                'ㄷ' | 'ㅅ' | 'ㅆ' | 'ㅌ' => 'ㅌ',
+                'ㅈ' | 'ㅊ' => 'ㅊ',
+                'ㅂ' => 'ㅍ',
+                _ => return,
+            };
+            // Palatalization: 굳히다 [구치다]
+            if next.initial == 'ㅌ' && matches!(next.vowel, 'ㅣ' | 'ㅕ') {
+                next.initial = 'ㅊ';
+            }
+            prev.coda = rest;
+        }
+    });
+}
+
+/// Carries a final consonant over to a following syllable that starts with a
+/// vowel (읽어 [일거]), palatalizing ㄷ and ㅌ before ㅣ (같이 [가치]).
+fn apply_liaison(pieces: &mut [Piece]) {
+    for_each_pair(pieces, |_, prev, next| {
+        let Some(coda) = prev.coda else {
+            return;
+        };
+        if next.initial != 'ㅇ' || coda == 'ㅇ' {
+            return;
+        }
+        let (rest, moved) = split_coda(coda);
+        prev.coda = rest;
+        next.initial = match (moved, next.vowel) {
+            ('ㄷ', 'ㅣ' | 'ㅕ') => 'ㅈ',
+            ('ㅌ', 'ㅣ' | 'ㅕ') => 'ㅊ',
+            (c, _) => c,
+        };
+    });
+}
+
+fn apply_neutralization(pieces: &mut [Piece]) {
+    let next_initials: Vec<Option<char>> = pieces.iter()
+        .skip(1)
+        .map(|p| match p {
+            Piece::Hangul(s) => Some(s.initial),
+            Piece::Other(_) => None,
+        })
+        .chain([None])
+        .collect();
+    for (piece, next_initial) in pieces.iter_mut().zip(next_initials) {
+        if let Piece::Hangul(s) = piece {
+            let syllable = *s;
+            s.coda = s.coda.map(|c| neutralize(&syllable, c, next_initial));
+        }
+    }
+}
+
+/// Tensifies plain consonants after ㄱ, ㄷ, ㅂ (학교 [학꾜]), and after
+/// consonant clusters at the end of verb stems (앉고 [안꼬]). `codas` holds the
+/// final consonants before neutralization.
+fn apply_tensification(pieces: &mut [Piece], codas: &[Option<char>]) {
+    for_each_pair(pieces, |i, prev, next| {
+        let tense = matches!(prev.coda, Some('ㄱ' | 'ㄷ' | 'ㅂ'))
+            || matches!(codas[i], Some('ㄵ' | 'ㄻ' | 'ㄼ' | 'ㄾ' | 'ㄺ'));
+        if tense {
+            if let Some(c) = tensify(next.initial) {
+                next.initial = c;
+            }
+        }
+    });
+}
+
+/// Lateralization (신라 [실라]) and the pronunciation of ㄹ as ㄴ after other
+/// consonants (침략 [침냑], 국립 [국닙]).
+fn apply_rieul_rules(pieces: &mut [Piece]) {
+    for_each_pair(pieces, |_, prev, next| {
+        match (prev.coda, next.initial) {
+            (Some('ㄴ'), 'ㄹ') => prev.coda = Some('ㄹ'),
+            (Some('ㄹ'), 'ㄴ') => next.initial = 'ㄹ',
+            (Some('ㄱ' | 'ㄷ' | 'ㅂ' | 'ㅁ' | 'ㅇ'), 'ㄹ') => next.initial = 'ㄴ',
+            _ => (),
+        }
+    });
+}
+
+/// ㄱ, ㄷ, ㅂ become nasal before ㄴ and ㅁ (먹는 [멍는], 합니다 [함니다]).
+fn apply_nasalization(pieces: &mut [Piece]) {
+    for_each_pair(pieces, |_, prev, next| {
+        if !matches!(next.initial, 'ㄴ' | 'ㅁ') {
+            return;
+        }
+        prev.coda = prev.coda.map(|c| match c {
+            'ㄱ' => 'ㅇ',
+            'ㄷ' => 'ㄴ',
+            'ㅂ' => 'ㅁ',
+            c => c,
+        });
+    });
+}
+
+fn pronounce_pieces(text: &str, tensification: bool) -> Vec<Piece> {
+    let mut pieces: Vec<Piece> = text.chars()
+        .map(|c| Syllable::decompose(c).map(Piece::Hangul).unwrap_or(Piece::Other(c)))
+        .collect();
+    apply_h_rules(&mut pieces);
+    apply_liaison(&mut pieces);
+    let codas: Vec<Option<char>> = pieces.iter()
+        .map(|p| match p {
+            Piece::Hangul(s) => s.coda,
+            Piece::Other(_) => None,
+        })
+        .collect();
+    apply_neutralization(&mut pieces);
+    if tensification {
+        apply_tensification(&mut pieces, &codas);
+    }
+    apply_rieul_rules(&mut pieces);
+    apply_nasalization(&mut pieces);
+    for piece in pieces.iter_mut() {
+        // ㅢ is pronounced ㅣ after a consonant (희망 [히망]).
+        if let Piece::Hangul(s) = piece {
+            if s.vowel == 'ㅢ' && s.initial != 'ㅇ' {
+                s.vowel = 'ㅣ';
+            }
+        }
+    }
+    pieces
+}
+
+/// Returns the standard pronunciation of `text`, written in Hangul (e.g.,
+/// "읽는다" => "잉는다"). Characters other than Hangul syllables are returned
+/// unchanged.
+pub fn pronounce(text: &str) -> String {
+    pronounce_pieces(text, true).iter()
+        .map(|p| match p {
+            Piece::Hangul(s) => s.compose(),
+            Piece::Other(c) => *c,
+        })
+        .collect()
+}
+
+/// Returns the Revised Romanization of the pronunciation of `text` (e.g.,
+/// "독립문" => "dongnimmun"). As in the official system, tensification is
+/// not reflected (e.g., "학교" => "hakgyo"). Characters other than Hangul syllables are returned unchanged.
+pub fn romanize(text: &str) -> String {
+    let mut out = String::new();
+    let mut prev_coda = None;
+    for piece in pronounce_pieces(text, false) {
+        match piece {
+            Piece::Hangul(s) => {
+                out += &s.romanize(prev_coda);
+                prev_coda = s.coda;
+            },
+            Piece::Other(c) => {
+                out.push(c);
+                prev_coda = None;
+            },
+        }
+    }
+    out
+}
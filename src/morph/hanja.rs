@@ -0,0 +1,75 @@
+use std::{collections::HashMap, path::Path};
+
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::Result;
+
+use super::chinese::is_han;
+
+#[derive(Debug, Deserialize)]
+struct HanjaRow {
+    word: String,
+    hanja: String,
+}
+
+/// Hanja spellings of Sino-Korean words, loaded from a CSV (or, if the file
+/// name ends with ".tsv", tab-separated) file with `word` and `hanja` columns.
+/// A word may appear on multiple lines if it has multiple spellings.
+#[derive(Debug, Default)]
+pub struct HanjaDict {
+    words: HashMap<String, Vec<String>>,
+    by_char: HashMap<char, Vec<String>>,
+}
+
+impl HanjaDict {
+    pub fn load(path: &Path) -> Result<Self> {
+        let delimiter = if path.extension().is_some_and(|ext| ext == "tsv") { b'\t' } else { b',' };
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .comment(Some(b'#'))
+            .from_path(path)?;
+        let mut dict = HanjaDict::default();
+        for row in rdr.deserialize() {
+            let row: HanjaRow = row?;
+            let (word, hanja) = (row.word.trim(), row.hanja.trim());
+            if word.is_empty() || hanja.is_empty() {
+                continue;
+            }
+            let spellings = dict.words.entry(word.to_string()).or_default();
+            if spellings.iter().any(|h| h == hanja) {
+                continue;
+            }
+            spellings.push(hanja.to_string());
+            for c in hanja.chars().filter(|c| is_han(*c)).unique() {
+                let words = dict.by_char.entry(c).or_default();
+                if !words.iter().any(|w| w == word) {
+                    words.push(word.to_string());
+                }
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Returns the Hanja spellings of `word`.
+    pub fn hanja(&self, word: &str) -> &[String] {
+        self.words.get(word).map(|h| h.as_slice()).unwrap_or_default()
+    }
+
+    /// Returns the Hanja spellings of `word` as shown to the user (e.g.,
+    /// "士氣 / 詐欺"), or `None` if it has none.
+    pub fn display(&self, word: &str) -> Option<String> {
+        let hanja = self.hanja(word);
+        (!hanja.is_empty()).then(|| hanja.join(" / "))
+    }
+
+    /// Returns the words whose Hanja spellings include the character `c`.
+    pub fn words_with(&self, c: char) -> &[String] {
+        self.by_char.get(&c).map(|w| w.as_slice()).unwrap_or_default()
+    }
+}
+
+/// Returns the distinct Hanja characters in `text`, in order.
+pub fn hanja_chars(text: &str) -> Vec<char> {
+    text.chars().filter(|c| is_han(*c)).unique().collect()
+}
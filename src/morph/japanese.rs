@@ -0,0 +1,207 @@
+use std::{collections::HashMap, ops::Range, path::PathBuf, sync::LazyLock};
+
+use anyhow::anyhow;
+use axum::async_trait;
+use notmecab::{Dict, LexerToken};
+use serde::{Deserialize, Serialize};
+
+use crate::{dict::{Word, WordStatus, EMPTY_WORD}, Result};
+
+use super::{korean::MecabConfig, Parser, Segment};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JapaneseDictionaryFormat {
+    #[default]
+    Ipadic,
+    Unidic,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JapaneseConfig {
+    mecab: MecabConfig,
+    #[serde(default)]
+    format: JapaneseDictionaryFormat,
+}
+
+impl JapaneseConfig {
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.mecab.files()
+    }
+}
+
+static POS_TAGS: LazyLock<HashMap<&'static str, Vec<&'static str>>> = LazyLock::new(|| HashMap::from([
+    ("名詞", vec!["noun"]),
+    ("代名詞", vec!["pronoun"]),
+    ("動詞", vec!["verb"]),
+    ("形容詞", vec!["adj"]),
+    ("形状詞", vec!["adj"]),
+    ("形容動詞", vec!["adj"]),
+    ("副詞", vec!["adv"]),
+    ("連体詞", vec!["prenoun"]),
+    ("接続詞", vec!["conjunction"]),
+    ("感動詞", vec!["interjection"]),
+    ("助詞", vec!["particle"]),
+]));
+
+#[derive(Clone, Debug)]
+struct Token {
+    range: Range<usize>,
+    text: String,
+    pos: Vec<String>,
+    lemma: Option<String>,
+    reading: Option<String>,
+}
+
+fn field(fields: &[&str], i: usize) -> Option<String> {
+    fields.get(i)
+        .filter(|f| !f.is_empty() && **f != "*")
+        .map(|f| f.to_string())
+}
+
+impl Token {
+    fn from_lexer_token(lexer_token: LexerToken, dict: &Dict, text: &str, format: JapaneseDictionaryFormat) -> Result<Self> {
+        let feature = lexer_token.get_feature(dict);
+        let fields = feature.split(',').collect::<Vec<_>>();
+        let range = lexer_token.range;
+        let surface = text.get(range.clone())
+            .ok_or_else(|| anyhow!("token range out of bounds: {range:?}"))?
+            .to_string();
+        let (lemma, reading) = match format {
+            // 品詞,品詞細分類1,品詞細分類2,品詞細分類3,活用型,活用形,原形,読み,発音
+            JapaneseDictionaryFormat::Ipadic => (field(&fields, 6), field(&fields, 7)),
+            // pos1,pos2,pos3,pos4,cType,cForm,lForm,lemma,orth,pron,orthBase,...
+            JapaneseDictionaryFormat::Unidic => {
+                let lemma = field(&fields, 10).or_else(|| {
+                    field(&fields, 7).map(|l| match l.split_once('-') {
+                        Some((l, _)) => l.to_string(),
+                        None => l,
+                    })
+                });
+                (lemma, field(&fields, 9))
+            },
+        };
+        let pos = fields.iter().take(4)
+            .filter(|f| **f != "*")
+            .map(|f| f.to_string())
+            .collect::<Vec<_>>();
+        if pos.is_empty() {
+            Err(anyhow!("token has no part of speech: {feature}"))?;
+        }
+        Ok(Token { range, text: surface, pos, lemma, reading })
+    }
+
+    fn is_word(&self) -> bool {
+        !matches!(self.pos[0].as_str(), "記号" | "補助記号" | "空白")
+    }
+
+    fn is_inflecting(&self) -> bool {
+        matches!(self.pos[0].as_str(), "動詞" | "形容詞" | "助動詞")
+    }
+
+    /// Whether this token should be attached to the preceding inflecting
+    /// word, rather than starting a new segment (e.g., the "た" in "食べた" or
+    /// the "いる" in "食べている").
+    fn is_auxiliary(&self) -> bool {
+        let pos = self.pos.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        match pos.as_slice() {
+            ["助動詞", ..] => true,
+            ["動詞" | "形容詞", "非自立", ..] => true,
+            ["動詞" | "形容詞", "非自立可能", ..] => true,
+            ["助詞", "接続助詞", ..] => self.text == "て" || self.text == "で",
+            _ => false,
+        }
+    }
+}
+
+pub struct JapaneseParser {
+    dict: Dict,
+    format: JapaneseDictionaryFormat,
+}
+
+impl JapaneseParser {
+    pub fn load(config: &JapaneseConfig) -> Result<Self> {
+        let dict = config.mecab.load()?;
+        Ok(JapaneseParser { dict, format: config.format })
+    }
+
+    fn tokens_to_word(&self, text: &str, tokens: &[Token]) -> Word {
+        let head = &tokens[0];
+        let text = text.to_string();
+        let tags: Vec<String> = POS_TAGS.get(head.pos[0].as_str())
+            .unwrap_or(&vec![])
+            .iter()
+            .map(|tag| tag.to_string())
+            .collect();
+        let pronunciation = tokens.iter()
+            .map(|t| t.reading.clone())
+            .collect::<Option<String>>()
+            .filter(|p| *p != text);
+
+        let mut debug = String::new();
+        for token in tokens {
+            debug += &format!("{}({}", token.text, token.pos.join(","));
+            if let Some(ref lemma) = token.lemma {
+                debug += &format!(",{lemma}");
+            }
+            debug += ")\n";
+        }
+        let debug = Some(debug.trim_end().to_string());
+
+        match head.lemma.clone() {
+            Some(lemma) if lemma != text => Word {
+                text,
+                pronunciation,
+                parents: vec![lemma],
+                tags,
+                debug,
+                inherit: true,
+                ..EMPTY_WORD
+            },
+            _ => Word {
+                text,
+                pronunciation,
+                tags,
+                debug,
+                status: Some(WordStatus::Unknown),
+                ..EMPTY_WORD
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Parser for JapaneseParser {
+    async fn parse(&self, text: &str) -> Result<Vec<Segment>> {
+        let (lexer_tokens, _) = self.dict.tokenize(text)?;
+        let mut tokens = vec![];
+        for lexer_token in lexer_tokens {
+            tokens.push(Token::from_lexer_token(lexer_token, &self.dict, text, self.format)?);
+        }
+
+        let mut groups: Vec<Vec<Token>> = vec![];
+        for token in tokens {
+            if !token.is_word() {
+                continue;
+            }
+            if let Some(group) = groups.last_mut() {
+                let last = group.last().unwrap();
+                let adjacent = last.range.end == token.range.start;
+                if adjacent && token.is_auxiliary() && (last.is_inflecting() || last.is_auxiliary()) {
+                    group.push(token);
+                    continue;
+                }
+            }
+            groups.push(vec![token]);
+        }
+
+        let mut segs = vec![];
+        for group in groups {
+            let range = group[0].range.start..group.last().unwrap().range.end;
+            let text = text[range.clone()].to_string();
+            let word = self.tokens_to_word(&text, &group);
+            segs.push(Segment { range, text, words: vec![word], sentence_end: false });
+        }
+        Ok(segs)
+    }
+}
@@ -1,17 +1,17 @@
-use std::{collections::{HashMap, VecDeque}, fmt::{Debug, Display}, mem::swap, ops::{Deref, Range}, str::FromStr, sync::LazyLock};
+use std::{collections::{BTreeSet, HashMap, VecDeque}, fmt::{Debug, Display}, mem::swap, ops::{Deref, Range}, path::{Path, PathBuf}, str::FromStr, sync::{Arc, LazyLock, RwLock}};
 
 use anyhow::anyhow;
 use async_stream::try_stream;
 use axum::async_trait;
 use figment::value::magic::RelativePathBuf;
-use futures::{Stream, StreamExt};
+use futures::{stream::{self, BoxStream}, Stream, StreamExt};
 use notmecab::{Blob, Dict, LexerToken};
 use serde::{Deserialize, Serialize};
 use serde_with::{skip_serializing_none, DeserializeFromStr};
 
-use crate::{dict::{Dictionary, Word, WordStatus, EMPTY_WORD}, time, Error, Result};
+use crate::{bad_req, check, dict::{Dictionary, Word, WordStatus, EMPTY_WORD}, overrides::ParseOverride, time, Error, Result};
 
-use super::{Parser, Segment};
+use super::{hangul, hanja::HanjaDict, maybe_string_to_list, Parser, Segment};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Element {
@@ -183,10 +183,38 @@ impl Display for Pattern {
     }
 }
 
+/// The type of a mecab-ko-dic entry, from the fifth field of its features.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TokenKind {
+    /// A single morpheme, or a token whose type isn't known (e.g., from an
+    /// override).
+    Simple,
+    /// An inflected form, e.g., "해서" (하/VV+아서/EC).
+    Inflect,
+    /// A compound, e.g., "가정주부" (가정/NNG+주부/NNG).
+    Compound,
+    /// An analysis of a phrase, e.g., a fixed expression.
+    Preanalysis,
+}
+
+impl TokenKind {
+    fn from_feature(field: &str) -> Self {
+        match field {
+            "Inflect" => Self::Inflect,
+            "Compound" => Self::Compound,
+            "Preanalysis" => Self::Preanalysis,
+            _ => Self::Simple,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct Token {
     range: Option<Range<usize>>,
     text: String,
+    kind: TokenKind,
+    /// The components of the token (e.g., the nouns of a compound noun), or
+    /// just the token itself for a simple token.
     pattern: Pattern,
 }
 
@@ -198,6 +226,7 @@ impl Token {
             Err(anyhow!("token expected to have 8 fields, got {}: {feature}", fields.len()))?;
         }
         let text = fields[3].to_string();
+        let kind = TokenKind::from_feature(fields[4]);
         let pattern = match fields[7] {
             "*" => Pattern(vec![Element {
                 text: text.clone(),
@@ -207,7 +236,7 @@ impl Token {
         };
 
         let range = Some(lexer_token.range);
-        Ok(Token { range, text, pattern })
+        Ok(Token { range, text, kind, pattern })
     }
 }
 
@@ -219,34 +248,33 @@ struct RuleRow {
     tags: Option<String>,
     translation: Option<String>,
     output: Option<Pattern>,
+    grammar: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 struct Rule {
+    line: u64,
     pattern: Pattern,
     parents: Vec<String>,
     tags: Vec<String>,
     translation: Option<String>,
     output: Option<Pattern>,
-}
-
-fn maybe_string_to_list(s: Option<String>) -> Vec<String> {
-    s
-        .filter(|p| !p.is_empty())
-        .map(|s| s.split(',').map(|t| t.trim().to_string()).collect())
-        .unwrap_or_default()
+    grammar: Vec<String>,
 }
 
 impl From<RuleRow> for Rule {
     fn from(row: RuleRow) -> Self {
         let parents = maybe_string_to_list(row.parents);
         let tags = maybe_string_to_list(row.tags);
+        let grammar = maybe_string_to_list(row.grammar);
         Self {
+            line: 0,
             pattern: row.pattern,
             parents,
             tags,
             translation: row.translation,
             output: row.output,
+            grammar,
         }
     }
 }
@@ -263,17 +291,105 @@ impl RuleTrie {
             rule: None,
         }
     }
+
+    /// Adds a rule to the trie, returning the rule it replaces, if any.
+    fn insert(&mut self, rule: Rule) -> Option<Rule> {
+        let mut node = self;
+        for element in rule.pattern.0.iter() {
+            node = node.children.entry(element.clone())
+                .or_insert_with(|| Box::new(RuleTrie::new()));
+        }
+        node.rule.replace(rule)
+    }
+
+    /// Applies the best matching rule to `pattern`, adding the rule's parents,
+    /// tags and grammar points. Returns `None` if no rule applies.
+    fn reduce_once(&self, pattern: &mut Pattern, parents: &mut Vec<String>, tags: &mut Vec<String>, grammar: &mut Vec<String>, debug: &mut String) -> Option<Reduction> {
+        if pattern.0.is_empty() {
+            None?;
+        }
+        // dbg!(&pattern);
+
+        let n = pattern.0.len();
+        for i in 0..n {
+            let mut rule = self.rule.as_ref();
+            let mut node = self;
+            for element in pattern.0[i..].iter() {
+                let Some(child) = node.children.get(element).map(|b| b.deref()) else {
+                    break;
+                };
+                node = child;
+                rule = child.rule.as_ref().or(rule);
+            }
+            let Some(rule) = rule else {
+                continue;
+            };
+            if rule.translation.is_some() && !(i == 0 && n == rule.pattern.0.len()) {
+                continue;
+            }
+            // dbg!(&rule);
+            *debug += &format!("\nApplying rule: {}", rule.pattern);
+            if let Some(output) = &rule.output {
+                *debug += &format!(" ==> {output}");
+            }
+            for parent in rule.parents.iter() {
+                if !parents.contains(parent) {
+                    *debug += &format!("\nAdding parent: {parent}");
+                    parents.push(parent.clone());
+                }
+            }
+            for tag in rule.tags.iter() {
+                if !tags.contains(tag) {
+                    *debug += &format!("\nAdding tag: {tag}");
+                    tags.push(tag.clone());
+                }
+            }
+            for point in rule.grammar.iter() {
+                if !grammar.contains(point) {
+                    *debug += &format!("\nAdding grammar point: {point}");
+                    grammar.push(point.clone());
+                }
+            }
+            if let Some(tr) = &rule.translation {
+                return Some(Reduction::Terminal(tr.clone()));
+            }
+            let j = i + rule.pattern.0.len();
+            let output = rule.output.clone().unwrap_or(Pattern(vec![]));
+            // dbg!((i, j, &output));
+            let mut elements = pattern.0.to_vec();
+            elements.splice(i..j, output.0.into_iter());
+            let mut new_pattern = Pattern(elements);
+            swap(pattern, &mut new_pattern);
+            return Some(Reduction::Step);
+        }
+
+        None
+    }
+}
+
+/// The term pattern rules, parse overrides and Hanja dictionary, which may be
+/// reloaded while the parser is in use.
+struct KoreanRules {
+    rules: RuleTrie,
+    overrides: HashMap<String, Vec<WordParsing>>,
+    hanja: Arc<HanjaDict>,
+    /// The grammar points that the rules refer to.
+    grammar: BTreeSet<String>,
 }
 
 pub struct KoreanParser {
     dict: Dict,
-    rules: RuleTrie,
+    rules: RwLock<Arc<KoreanRules>>,
+    db_overrides: RwLock<Arc<HashMap<String, Vec<WordParsing>>>>,
     lit_dict: Dictionary,
-    overrides: HashMap<String, Vec<WordParsing>>,
+    pronunciation: KoreanPronunciation,
+    config: KoreanConfig,
 }
 
 pub struct KoreanParseOutput<'a> {
     parser: &'a KoreanParser,
+    rules: Arc<KoreanRules>,
+    db_overrides: Arc<HashMap<String, Vec<WordParsing>>>,
     tokens: VecDeque<Token>,
     next: Option<Segment>,
 }
@@ -312,16 +428,44 @@ impl<'a> KoreanParseOutput<'a> {
         };
 
         let mut t = std::time::Duration::ZERO;
-        let mut words = vec![];
-        if let Some(overrides) = self.parser.overrides.get(&text) {
-            for ovr in overrides {
-                words.push(time!(t, self.parser.tokens_to_word(&text, ovr.tokens.clone()).await)?);
+        let mut parsings = vec![];
+        // Overrides entered from the reader replace those from the overrides
+        // file.
+        let overrides = self.db_overrides.get(&text)
+            .or_else(|| self.rules.overrides.get(&text));
+        if let Some(overrides) = overrides {
+            parsings.extend(overrides.iter().map(|ovr| ovr.tokens.clone()));
+        }
+        let alternatives = self.parser.alternative_tokens(&text, &tokens)?;
+        let sentence_end = tokens.last()
+            .and_then(|t| t.pattern.0.last())
+            .is_some_and(|e| e.pos == "EF");
+        parsings.push(tokens);
+        parsings.extend(alternatives);
+
+        let mut words: Vec<Word> = vec![];
+        for tokens in parsings {
+            let word = time!(t, self.parser.tokens_to_word(&self.rules, &text, tokens).await)?;
+            // Different parsings may still produce the same word (e.g., an
+            // override which matches what mecab found).
+            let duplicate = words.iter().any(|w| {
+                w.parents == word.parents && w.tags == word.tags && w.translation == word.translation
+            });
+            if !duplicate {
+                words.push(word);
             }
         }
 
-        // FIXME: this could be a duplicate of one of the overrides.
-        words.push(time!(t, self.parser.tokens_to_word(&text, tokens).await)?);
-        Ok(Some(Segment { range, text, words }))
+        let pronunciation = self.parser.pronounce(&text);
+        for word in words.iter_mut() {
+            word.pronunciation.clone_from(&pronunciation);
+            // An inflected word is spelled with the Hanja of its root.
+            word.hanja = match &word.parents[..] {
+                [root] => self.rules.hanja.display(&text).or_else(|| self.rules.hanja.display(root)),
+                _ => self.rules.hanja.display(&text),
+            };
+        }
+        Ok(Some(Segment { range, text, words, sentence_end }))
     }
 }
 
@@ -333,11 +477,88 @@ pub struct MecabConfig {
     pub char: RelativePathBuf,
 }
 
+impl MecabConfig {
+    pub fn load(&self) -> Result<Dict> {
+        let sysdic = Blob::open(self.sysdic.relative())?;
+        let unkdic = Blob::open(self.unkdic.relative())?;
+        let matrix = Blob::open(self.matrix.relative())?;
+        let char = Blob::open(self.char.relative())?;
+        let dict = Dict::load(sysdic, unkdic, matrix, char)
+            .map_err(|e| anyhow!("failed to load mecab dictionary: {e}"))?;
+        Ok(dict)
+    }
+
+    pub fn files(&self) -> Vec<PathBuf> {
+        vec![
+            self.sysdic.relative(),
+            self.unkdic.relative(),
+            self.matrix.relative(),
+            self.char.relative(),
+        ]
+    }
+}
+
+/// How the pronunciation of generated words is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KoreanPronunciation {
+    /// Don't generate pronunciations.
+    Off,
+    /// Hangul, with sound changes applied (e.g., "읽는다" => "잉는다").
+    #[default]
+    Hangul,
+    /// Revised Romanization (e.g., "읽는다" => "ingneunda").
+    Romanized,
+    /// Both Hangul and Revised Romanization (e.g., "[잉는다] ingneunda").
+    Both,
+}
+
+impl KoreanPronunciation {
+    /// Returns the pronunciation of `text`, or `None` if it is pronounced as
+    /// written.
+    pub fn pronounce(&self, text: &str) -> Option<String> {
+        let pronunciation = match self {
+            Self::Off => None,
+            Self::Hangul => Some(hangul::pronounce(text)),
+            Self::Romanized => Some(hangul::romanize(text)),
+            Self::Both => Some(hangul::romanize(text))
+                .filter(|r| r != text)
+                .map(|r| format!("[{}] {r}", hangul::pronounce(text))),
+        };
+        pronunciation.filter(|p| p != text)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct KoreanConfig {
     mecab: MecabConfig,
     patterns: RelativePathBuf,
     overrides: Option<RelativePathBuf>,
+    hanja: Option<RelativePathBuf>,
+    #[serde(default)]
+    pub pronunciation: KoreanPronunciation,
+    /// How many alternative parsings of each word (besides the best one) to
+    /// propose as candidate words. Mecab doesn't give the n best parsings, so
+    /// these are chosen heuristically (see `KoreanParser::alternative_tokens`),
+    /// and each one costs extra dictionary lookups when it is normalized.
+    #[serde(default)]
+    alternatives: usize,
+}
+
+impl KoreanConfig {
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = self.mecab.files();
+        files.extend(self.reloadable_files());
+        files
+    }
+
+    /// Returns the files that `KoreanParser::reload` loads again.
+    pub fn reloadable_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![self.patterns.relative()];
+        files.extend(self.overrides.iter().map(|p| p.relative()));
+        files.extend(self.hanja.iter().map(|p| p.relative()));
+        files
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -364,55 +585,211 @@ impl FromStr for WordParsing {
             };
             let text = text.to_string();
             let pattern = Pattern::from_str(pattern)?;
-            let token = Token { range: None, text, pattern };
+            let token = Token { range: None, text, kind: TokenKind::Simple, pattern };
             tokens.push(token);
         }
         Ok(Self { tokens })
     }
 }
 
-impl KoreanParser {
-    pub fn load(config: &KoreanConfig, lit_dict: Dictionary) -> Result<Self> {
-        let sysdic = Blob::open(config.mecab.sysdic.relative())?;
-        let unkdic = Blob::open(config.mecab.unkdic.relative())?;
-        let matrix = Blob::open(config.mecab.matrix.relative())?;
-        let char = Blob::open(config.mecab.char.relative())?;
-        let dict = Dict::load(sysdic, unkdic, matrix, char)
-            .map_err(|e| anyhow!("failed to load mecab dictionary: {e}"))?;
+/// Checks that `parsing` is a valid override for how `word` is parsed, in the
+/// same format as the overrides file.
+pub fn validate_parsing(word: &str, parsing: &str) -> Result<()> {
+    let parsing = match WordParsing::from_str(parsing) {
+        Ok(parsing) => parsing,
+        Err(e) => return bad_req(&format!("invalid parsing: {e}")),
+    };
+    let text = parsing.tokens.iter().map(|t| t.text.as_str()).collect::<String>();
+    check(text == word, &format!("parsing does not match word: {text}"))
+}
+
+fn read_rules(path: &Path) -> Result<Vec<Rule>> {
+    let mut rules = vec![];
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .comment(Some(b'#'))
+        .from_path(path)?;
+    let headers = rdr.headers()?.clone();
+    for record in rdr.records() {
+        let record = record?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        let row: RuleRow = record.deserialize(Some(&headers))?;
+        rules.push(Rule { line, ..row.into() });
+    }
+    Ok(rules)
+}
+
+fn read_overrides(path: &Path) -> Result<Vec<(u64, OverrideRow)>> {
+    let mut overrides = vec![];
+    let mut rdr = csv::ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .from_path(path)?;
+    let headers = rdr.headers()?.clone();
+    for record in rdr.records() {
+        let record = record?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        overrides.push((line, record.deserialize(Some(&headers))?));
+    }
+    Ok(overrides)
+}
 
+impl KoreanRules {
+    fn load(config: &KoreanConfig) -> Result<Self> {
         let mut rules = RuleTrie::new();
-        let mut rdr = csv::ReaderBuilder::new()
-            .flexible(true)
-            .comment(Some(b'#'))
-            .from_path(config.patterns.relative())?;
-        for rule in rdr.deserialize::<RuleRow>() {
-            let rule: Rule = rule?.into();
-            let mut node = &mut rules;
-            for element in rule.pattern.0.iter() {
-                node = node.children.entry(element.clone())
-                    .or_insert_with(|| Box::new(RuleTrie::new()));
+        let mut grammar = BTreeSet::new();
+        for rule in read_rules(&config.patterns.relative())? {
+            grammar.extend(rule.grammar.iter().cloned());
+            if let Some(old) = rules.insert(rule) {
+                println!("WARNING: duplicate term pattern rule: {}", old.pattern);
             }
-            if node.rule.is_some() {
-                println!("WARNING: duplicate term pattern rule: {}", rule.pattern);
-            }
-            let _ = node.rule.insert(rule);
         }
         
         let mut overrides: HashMap<String, Vec<WordParsing>> = HashMap::new();
         if let Some(ref path) = config.overrides {
-            let mut rdr = csv::ReaderBuilder::new()
-                .comment(Some(b'#'))
-                .from_path(path.relative())?;
-            for ovr in rdr.deserialize::<OverrideRow>() {
-                let ovr = ovr?;
+            for (_, ovr) in read_overrides(&path.relative())? {
                 overrides.entry(ovr.word).or_default().push(ovr.parsing);
             }
         }
 
-        Ok(KoreanParser { dict, rules, lit_dict, overrides })
+        let hanja = match config.hanja {
+            Some(ref path) => HanjaDict::load(&path.relative())?,
+            None => HanjaDict::default(),
+        };
+
+        Ok(KoreanRules { rules, overrides, hanja: Arc::new(hanja), grammar })
+    }
+}
+
+impl KoreanParser {
+    pub fn load(config: &KoreanConfig, lit_dict: Dictionary) -> Result<Self> {
+        let dict = config.mecab.load()?;
+        let rules = RwLock::new(Arc::new(KoreanRules::load(config)?));
+        let db_overrides = RwLock::default();
+        Ok(KoreanParser { dict, rules, db_overrides, lit_dict, pronunciation: config.pronunciation, config: config.clone() })
     }
 
-    fn parse_stream<'a>(&'a self, text: &'a str) -> Result<impl Stream<Item = Result<Segment>> + 'a> {
+    /// Replaces the overrides stored in the database. Overrides that can't be
+    /// parsed are skipped with a warning.
+    pub fn set_overrides(&self, overrides: &[ParseOverride]) {
+        let mut map: HashMap<String, Vec<WordParsing>> = HashMap::new();
+        for ovr in overrides {
+            match WordParsing::from_str(&ovr.parsing) {
+                Ok(parsing) => map.entry(ovr.word.clone()).or_default().push(parsing),
+                Err(e) => println!("WARNING: invalid parse override {} for {}: {e}", ovr.id, ovr.word),
+            }
+        }
+        *self.db_overrides.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(map);
+    }
+
+    /// Reloads the term patterns and overrides files. Parses that are already
+    /// in progress continue to use the old rules.
+    pub fn reload(&self) -> Result<()> {
+        let rules = Arc::new(KoreanRules::load(&self.config)?);
+        *self.rules.write().unwrap_or_else(|e| e.into_inner()) = rules;
+        Ok(())
+    }
+
+    fn rules(&self) -> Arc<KoreanRules> {
+        self.rules.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Returns the pronunciation of `text` in the configured style, or `None`
+    /// if it is pronounced as written.
+    pub fn pronounce(&self, text: &str) -> Option<String> {
+        self.pronunciation.pronounce(text)
+    }
+
+    /// Returns the Hanja dictionary, which is empty if none is configured.
+    pub fn hanja(&self) -> Arc<HanjaDict> {
+        self.rules().hanja.clone()
+    }
+
+    /// Returns the names of the grammar points referred to by the term
+    /// pattern rules, in order.
+    pub fn grammar_points(&self) -> Vec<String> {
+        self.rules().grammar.iter().cloned().collect()
+    }
+
+    /// Tokenizes `text` on its own, returning the tokens and the total cost.
+    fn tokenize_word(&self, text: &str) -> Result<(Vec<Token>, i64)> {
+        let (lexer_tokens, cost) = self.dict.tokenize(text)?;
+        let mut tokens = vec![];
+        for token in lexer_tokens {
+            let token = Token::from_lexer_token(token, &self.dict)?;
+            if token.text == "*" {
+                continue;
+            }
+            tokens.push(token);
+        }
+        Ok((tokens, cost))
+    }
+
+    /// Tokenizes `text` on its own with a token boundary forced at byte offset
+    /// `split`, returning the tokens and the total cost, or `None` if no path
+    /// has a boundary there.
+    ///
+    /// The boundary is forced by inserting a space, which mecab skips while
+    /// still connecting the tokens on either side of it, so the cost is that of
+    /// a single path through the lattice (with one BOS and one EOS connection)
+    /// and can be compared with that of other paths.
+    fn tokenize_split(&self, text: &str, split: usize) -> Result<Option<(Vec<Token>, i64)>> {
+        let spaced = format!("{} {}", &text[..split], &text[split..]);
+        let (mut tokens, cost) = self.tokenize_word(&spaced)?;
+        for token in tokens.iter_mut() {
+            let Some(range) = token.range.clone() else {
+                continue;
+            };
+            if range.start <= split && range.end > split {
+                return Ok(None);
+            }
+            let unshift = |i: usize| if i > split { i - 1 } else { i };
+            token.range = Some(unshift(range.start)..unshift(range.end));
+        }
+        Ok(Some((tokens, cost)))
+    }
+
+    /// Returns up to `alternatives` (see `KoreanConfig`) other ways to
+    /// tokenize the word `text`, besides `best`, cheapest first.
+    ///
+    /// Mecab only gives us the best path through the lattice, so this is a
+    /// heuristic rather than an n-best search: the candidates are the best
+    /// path for the word on its own, and the best path with a token boundary
+    /// forced at each character boundary (see `tokenize_split`).
+    fn alternative_tokens(&self, text: &str, best: &[Token]) -> Result<Vec<Vec<Token>>> {
+        let n = self.config.alternatives;
+        if n == 0 {
+            return Ok(vec![]);
+        }
+        let key = |tokens: &[Token]| tokens.iter()
+            .map(|t| format!("{}({})", t.text, t.pattern))
+            .collect::<Vec<_>>()
+            .join(";");
+        let mut seen = vec![key(best)];
+        let mut candidates = vec![];
+        for (split, _) in text.char_indices() {
+            let tokenized = if split == 0 {
+                Some(self.tokenize_word(text)?)
+            } else {
+                self.tokenize_split(text, split)?
+            };
+            let Some((tokens, cost)) = tokenized else {
+                continue;
+            };
+            if tokens.is_empty() || !tokens.iter().any(|t| t.pattern.is_word()) {
+                continue;
+            }
+            let k = key(&tokens);
+            if seen.contains(&k) {
+                continue;
+            }
+            seen.push(k);
+            candidates.push((cost, tokens));
+        }
+        candidates.sort_by_key(|(cost, _)| *cost);
+        Ok(candidates.into_iter().take(n).map(|(_, tokens)| tokens).collect())
+    }
+
+    fn segment_stream<'a>(&'a self, text: &'a str) -> Result<impl Stream<Item = Result<Segment>> + Send + 'a> {
         let mut tokens = VecDeque::new();
         let result = self.dict.tokenize(text)?;
         let mut last_end = 0;
@@ -431,7 +808,7 @@ impl KoreanParser {
                     pos: "UNK".to_string(),
                 }]);
                 let range = Some(range);
-                tokens.push_back(Token { range, text: sep, pattern });
+                tokens.push_back(Token { range, text: sep, kind: TokenKind::Simple, pattern });
             }
             last_end = token_range.end;
             tokens.push_back(token);
@@ -444,10 +821,11 @@ impl KoreanParser {
                 pos: "UNK".to_string(),
             }]);
             let range = Some(range);
-            tokens.push_back(Token { range, text: sep, pattern });
+            tokens.push_back(Token { range, text: sep, kind: TokenKind::Simple, pattern });
         }
 
-        let mut output = KoreanParseOutput { parser: self, tokens, next: None };
+        let db_overrides = self.db_overrides.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut output = KoreanParseOutput { parser: self, rules: self.rules(), db_overrides, tokens, next: None };
         Ok(try_stream! {
             while let Some(seg) = output.next().await? {
                 yield seg;
@@ -467,6 +845,8 @@ struct RootProposal {
     tokens_consumed: usize,
     extra_element: bool,
     in_dict: bool,
+    /// The nouns making up the root, if it's a compound noun.
+    components: Vec<String>,
 }
 
 impl RootProposal {
@@ -521,12 +901,22 @@ impl RootProposal {
             word += "다";
         }
         let word = word;
+        let components = match (prefix, extra) {
+            ([token], None) if is_noun && token.kind == TokenKind::Compound => {
+                token.pattern.0.iter()
+                    .filter(|e| is_noun_pos(&e.pos))
+                    .map(|e| e.text.clone())
+                    .collect()
+            },
+            _ => vec![],
+        };
         Some(RootProposal {
             word,
             pos,
             tokens_consumed: prefix.len(),
             extra_element: extra.is_some(),
             in_dict: false,
+            components,
         })
     }
 
@@ -565,13 +955,20 @@ where
 }
 
 impl KoreanParser {
-    async fn normalize(&self, tokens: Vec<Token>) -> Result<(String, Pattern)> {
+    /// Finds the root word at the start of the tokens, and returns it along
+    /// with the rest of the pattern and, if the root is a compound noun that
+    /// isn't in the dictionary, its components.
+    async fn normalize(&self, tokens: Vec<Token>) -> Result<(String, Pattern, Vec<String>)> {
         let mut t = std::time::Duration::ZERO;
         let mut root: Option<RootProposal> = None;
         for (i, token) in tokens.iter().enumerate() {
-            if let Some(mut proposal) = RootProposal::build(&tokens[0..i], token.pattern.0.first()) {
-                proposal.in_dict = time!(t, self.lit_dict.word_exists(&proposal.word).await)?;
-                set_or_compare(&mut root, proposal, |old, new| new.preferred_over(old));
+            // Compounds are kept whole, so that their components become
+            // parents rather than one of them becoming the root.
+            if token.kind != TokenKind::Compound {
+                if let Some(mut proposal) = RootProposal::build(&tokens[0..i], token.pattern.0.first()) {
+                    proposal.in_dict = time!(t, self.lit_dict.word_exists(&proposal.word).await)?;
+                    set_or_compare(&mut root, proposal, |old, new| new.preferred_over(old));
+                }
             }
             if let Some(mut proposal) = RootProposal::build(&tokens[0..=i], None) {
                 proposal.in_dict = time!(t, self.lit_dict.word_exists(&proposal.word).await)?;
@@ -585,7 +982,7 @@ impl KoreanParser {
             if pattern.0[0].pos.starts_with('V') {
                 root += "다";
             }
-            return Ok((root, pattern));
+            return Ok((root, pattern, vec![]));
         };
 
         let mut tokens = tokens.into_iter().skip(root.tokens_consumed);
@@ -595,11 +992,12 @@ impl KoreanParser {
         }
         elements.extend(tokens.flat_map(|t| t.pattern.0));
         let pattern = Pattern(elements);
+        let components = if root.in_dict { vec![] } else { root.components };
 
-        Ok((root.word, pattern))
+        Ok((root.word, pattern, components))
     }
 
-    async fn tokens_to_word(&self, text: &str, tokens: Vec<Token>) -> Result<Word> {
+    async fn tokens_to_word(&self, rules: &KoreanRules, text: &str, tokens: Vec<Token>) -> Result<Word> {
         if tokens.is_empty() {
             Err(anyhow!("word has no tokens"))?;
         }
@@ -608,9 +1006,12 @@ impl KoreanParser {
         debug += &format!("Initial pattern: {}", tokens.iter().map(|t| format!("{}({})", t.text, t.pattern)).collect::<Vec<_>>().join(";"));
 
         let mut t = std::time::Duration::ZERO;
-        let (root, mut pattern) = time!(t, self.normalize(tokens).await)?;
+        let (root, mut pattern, components) = time!(t, self.normalize(tokens).await)?;
 
         debug += &format!("\nAfter normalization: {pattern}\nRoot word: {root}");
+        if !components.is_empty() {
+            debug += &format!("\nRoot is a compound of: {}", components.join(", "));
+        }
 
         let last_pos = pattern.0.last().unwrap().pos.clone();
         let mut tags: Vec<String> = POS_TAGS.get(last_pos.as_str())
@@ -626,20 +1027,26 @@ impl KoreanParser {
         if pattern.0.len() == 1 || root == text {
             debug += "\nWord is a root word";
             let debug = Some(debug);
+            if !components.is_empty() {
+                return Ok(Word { text, parents: components, tags, debug, inherit: true, ..EMPTY_WORD });
+            }
             return Ok(Word { text, tags, debug, status: Some(WordStatus::Unknown), ..EMPTY_WORD });
         }
 
-        let mut parents = vec![root];
+        // A compound noun that isn't in the dictionary is replaced by its
+        // components, so that its status derives from theirs.
+        let mut parents = if components.is_empty() { vec![root] } else { components };
 
+        let mut grammar = vec![];
         let mut t = std::time::Duration::ZERO;
-        while let Some(r) = time!(t, self.reduce_pattern_once(&mut pattern, &mut parents, &mut tags, &mut debug)) {
+        while let Some(r) = time!(t, rules.rules.reduce_once(&mut pattern, &mut parents, &mut tags, &mut grammar, &mut debug)) {
             debug += &format!("\n  ==> {pattern}");
             match r {
                 Reduction::Step => (),
                 Reduction::Terminal(translation) => {
                     debug += &format!("\nWord translated as '{translation}'");
                     let debug = Some(debug);
-                    return Ok(Word { text, parents, tags, debug, translation, inherit: true, ..EMPTY_WORD });
+                    return Ok(Word { text, parents, tags, grammar, debug, translation, inherit: true, ..EMPTY_WORD });
                 },
             }
         }
@@ -647,74 +1054,214 @@ impl KoreanParser {
         debug += "\nPattern is irreducible";
         let debug = Some(debug);
 
-        Ok(Word { text, parents, tags, debug, status: Some(WordStatus::Unknown), translation: format!("`{pattern}`"), ..EMPTY_WORD })
+        Ok(Word { text, parents, tags, grammar, debug, status: Some(WordStatus::Unknown), translation: format!("`{pattern}`"), ..EMPTY_WORD })
     }
+}
 
-    fn reduce_pattern_once(&self, pattern: &mut Pattern, parents: &mut Vec<String>, tags: &mut Vec<String>, debug: &mut String) -> Option<Reduction> {
-        if pattern.0.is_empty() {
-            None?;
+#[async_trait]
+impl Parser for KoreanParser {
+    async fn parse(&self, text: &str) -> Result<Vec<Segment>> {
+        let mut segs = vec![];
+        let mut stream = Box::pin(self.segment_stream(text)?);
+        while let Some(seg) = stream.next().await {
+            segs.push(seg?);
         }
-        // dbg!(&pattern);
+        Ok(segs)
+    }
 
-        let n = pattern.0.len();
-        for i in 0..n {
-            let mut rule = self.rules.rule.as_ref();
-            let mut node = &self.rules;
-            for element in pattern.0[i..].iter() {
-                let Some(child) = node.children.get(element).map(|b| b.deref()) else {
-                    break;
-                };
-                node = child;
-                rule = child.rule.as_ref().or(rule);
+    fn parse_stream<'a>(&'a self, text: &'a str) -> BoxStream<'a, Result<Segment>> {
+        match self.segment_stream(text) {
+            Ok(segs) => segs.boxed(),
+            Err(err) => stream::once(async { Err(err) }).boxed(),
+        }
+    }
+}
+
+/// Parts of speech used by mecab-ko-dic.
+const MECAB_POS: [&str; 44] = [
+    "NNG", "NNP", "NNB", "NNBC", "NR", "NP", "VV", "VA", "VX", "VCP", "VCN",
+    "MM", "MAG", "MAJ", "IC", "JKS", "JKC", "JKG", "JKO", "JKB", "JKV", "JKQ",
+    "JX", "JC", "EP", "EF", "EC", "ETN", "ETM", "XPN", "XSN", "XSV", "XSA",
+    "XR", "SF", "SE", "SSO", "SSC", "SC", "SY", "SL", "SH", "SN", "UNK",
+];
+
+fn is_mecab_pos(pos: &str) -> bool {
+    MECAB_POS.iter().any(|p| *p == pos)
+}
+
+/// Maximum number of rewrites to apply to a pattern before assuming that the
+/// rules don't terminate.
+const MAX_REWRITES: usize = 100;
+
+/// A problem found in a term patterns, overrides, or golden corpus file.
+#[derive(Clone, Debug)]
+pub struct LintIssue {
+    pub path: PathBuf,
+    pub line: u64,
+    pub message: String,
+}
+
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GoldenRow {
+    word: String,
+    parents: Option<String>,
+    tags: Option<String>,
+    translation: Option<String>,
+}
+
+impl KoreanConfig {
+    /// Checks the term patterns and overrides files for rules that are
+    /// duplicated, can never be applied, or rewrite patterns endlessly, and for
+    /// unknown parts of speech.
+    pub fn lint(&self) -> Result<Vec<LintIssue>> {
+        let mut issues = vec![];
+        let path = self.patterns.relative();
+        let issue = |line, message| LintIssue { path: path.clone(), line, message };
+        let rules = read_rules(&path)?;
+
+        // Parts of speech that may appear in a pattern, besides those produced
+        // by mecab.
+        let mut produced = vec!["NN", "VV", "VA"];
+        produced.extend(rules.iter()
+            .flat_map(|r| r.output.iter())
+            .flat_map(|p| p.0.iter())
+            .map(|e| e.pos.as_str()));
+        let is_produced = |pos: &str| produced.iter().any(|p| *p == pos);
+        let known_pos = |pos: &str| is_mecab_pos(pos) || is_produced(pos);
+        let removes_root = rules.iter().any(|r| {
+            r.translation.is_none() &&
+            r.pattern.0[0].text == "*" &&
+            r.output.as_ref().and_then(|o| o.0.first()).map(|e| e.text != "*").unwrap_or(true)
+        });
+
+        let mut trie = RuleTrie::new();
+        for rule in rules.iter() {
+            if let Some(old) = trie.insert(rule.clone()) {
+                issues.push(issue(old.line, format!("rule `{}` is overridden by the duplicate rule on line {}", old.pattern, rule.line)));
             }
-            let Some(rule) = rule else {
-                continue;
-            };
-            if rule.translation.is_some() && !(i == 0 && n == rule.pattern.0.len()) {
-                continue;
+            if rule.translation.is_some() && rule.output.is_some() {
+                issues.push(issue(rule.line, format!("rule `{}` has both a translation and an output; the output is ignored", rule.pattern)));
             }
-            // dbg!(&rule);
-            *debug += &format!("\nApplying rule: {}", rule.pattern);
-            if let Some(output) = &rule.output {
-                *debug += &format!(" ==> {output}");
+            if rule.translation.is_some() && rule.pattern.0[0].text != "*" && !removes_root {
+                issues.push(issue(rule.line, format!("translation rule `{}` can never fire: it must match the whole word, which always starts with the root (`*`)", rule.pattern)));
             }
-            for parent in rule.parents.iter() {
-                if !parents.contains(parent) {
-                    *debug += &format!("\nAdding parent: {parent}");
-                    parents.push(parent.clone());
+            for (i, element) in rule.pattern.0.iter().enumerate() {
+                if !known_pos(&element.pos) {
+                    issues.push(issue(rule.line, format!("unknown part of speech `{}` in pattern `{}`", element.pos, rule.pattern)));
+                } else if i > 0 && element.text == "*" {
+                    issues.push(issue(rule.line, format!("rule `{}` can never fire: `*` only matches the root, which is always the first element", rule.pattern)));
+                } else if i == 0 && element.text == "*" && !is_produced(&element.pos) && matches!(element.pos.as_str(), "VX" | "XSV" | "XSA" | "XSN" | "ETN") {
+                    issues.push(issue(rule.line, format!("rule `{}` can never fire: the root part of speech `{}` is always normalized", rule.pattern, element.pos)));
                 }
             }
-            for tag in rule.tags.iter() {
-                if !tags.contains(tag) {
-                    *debug += &format!("\nAdding tag: {tag}");
-                    tags.push(tag.clone());
+            for element in rule.output.iter().flat_map(|p| p.0.iter()) {
+                if !known_pos(&element.pos) {
+                    issues.push(issue(rule.line, format!("unknown part of speech `{}` in output `{}`", element.pos, rule.output.as_ref().unwrap())));
                 }
             }
-            if let Some(tr) = &rule.translation {
-                return Some(Reduction::Terminal(tr.clone()));
+        }
+
+        for rule in rules.iter().filter(|r| r.translation.is_none()) {
+            let mut pattern = rule.pattern.clone();
+            let mut seen = vec![pattern.clone()];
+            let mut terminated = false;
+            for _ in 0..MAX_REWRITES {
+                match trie.reduce_once(&mut pattern, &mut vec![], &mut vec![], &mut vec![], &mut String::new()) {
+                    None | Some(Reduction::Terminal(_)) => {
+                        terminated = true;
+                        break;
+                    },
+                    Some(Reduction::Step) => (),
+                }
+                if let Some(start) = seen.iter().position(|p| *p == pattern) {
+                    let cycle = seen[start..].iter().chain([&pattern]).map(|p| format!("`{p}`")).collect::<Vec<_>>();
+                    issues.push(issue(rule.line, format!("rewrite loop: {}", cycle.join(" ==> "))));
+                    terminated = true;
+                    break;
+                }
+                seen.push(pattern.clone());
+            }
+            if !terminated {
+                issues.push(issue(rule.line, format!("rule `{}` is rewritten more than {MAX_REWRITES} times", rule.pattern)));
             }
-            let j = i + rule.pattern.0.len();
-            let output = rule.output.clone().unwrap_or(Pattern(vec![]));
-            // dbg!((i, j, &output));
-            let mut elements = pattern.0.to_vec();
-            elements.splice(i..j, output.0.into_iter());
-            let mut new_pattern = Pattern(elements);
-            swap(pattern, &mut new_pattern);
-            return Some(Reduction::Step);
         }
 
-        None
+        if let Some(ref path) = self.overrides {
+            let path = path.relative();
+            for (line, ovr) in read_overrides(&path)? {
+                let issue = |message| LintIssue { path: path.clone(), line, message };
+                let text = ovr.parsing.tokens.iter().map(|t| t.text.as_str()).collect::<String>();
+                if text != ovr.word {
+                    issues.push(issue(format!("parsing of `{}` does not match the word: `{text}`", ovr.word)));
+                }
+                for element in ovr.parsing.tokens.iter().flat_map(|t| t.pattern.0.iter()) {
+                    if !is_mecab_pos(&element.pos) {
+                        issues.push(issue(format!("unknown part of speech `{}` in parsing of `{}`", element.pos, ovr.word)));
+                    }
+                }
+            }
+        }
+
+        issues.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+        Ok(issues)
     }
 }
 
-#[async_trait]
-impl Parser for KoreanParser {
-    async fn parse(&self, text: &str) -> Result<Vec<Segment>> {
-        let mut segs = vec![];
-        let mut stream = Box::pin(self.parse_stream(text)?);
-        while let Some(seg) = stream.next().await {
-            segs.push(seg?);
+impl KoreanParser {
+    /// Parses each word in the given golden corpus file, and checks that the
+    /// generated word has the expected parents, tags, and translation. The
+    /// overrides stored in the database must have been set (see
+    /// `set_overrides`) for the parser to match the reader's.
+    pub async fn check_golden(&self, path: &Path) -> Result<Vec<LintIssue>> {
+        let mut issues = vec![];
+        let mut rdr = csv::ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .from_path(path)?;
+        let headers = rdr.headers()?.clone();
+        for record in rdr.records() {
+            let record = record?;
+            let line = record.position().map(|p| p.line()).unwrap_or_default();
+            let row: GoldenRow = record.deserialize(Some(&headers))?;
+            let issue = |message| LintIssue { path: path.to_path_buf(), line, message };
+
+            let segs = self.parse(&row.word).await?;
+            let word = match &segs[..] {
+                [seg] if seg.text == row.word => seg.words.first(),
+                _ => None,
+            };
+            let Some(word) = word else {
+                let texts = segs.iter().map(|s| s.text.as_str()).collect::<Vec<_>>();
+                issues.push(issue(format!("{}: expected a single word, got {texts:?}", row.word)));
+                continue;
+            };
+
+            let mut expected_parents = maybe_string_to_list(row.parents);
+            let mut parents = word.parents.clone();
+            expected_parents.sort();
+            parents.sort();
+            if parents != expected_parents {
+                issues.push(issue(format!("{}: expected parents {expected_parents:?}, got {parents:?}", row.word)));
+            }
+
+            let mut expected_tags = maybe_string_to_list(row.tags);
+            let mut tags = word.tags.clone();
+            expected_tags.sort();
+            tags.sort();
+            if tags != expected_tags {
+                issues.push(issue(format!("{}: expected tags {expected_tags:?}, got {tags:?}", row.word)));
+            }
+
+            let expected_translation = row.translation.unwrap_or_default();
+            if word.translation != expected_translation {
+                issues.push(issue(format!("{}: expected translation {expected_translation:?}, got {:?}", row.word, word.translation)));
+            }
         }
-        Ok(segs)
+        Ok(issues)
     }
 }
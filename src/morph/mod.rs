@@ -1,19 +1,43 @@
-use std::{collections::BTreeMap, ops::Range};
+use std::{collections::{BTreeMap, HashMap}, fmt, ops::Range, path::PathBuf, sync::{Arc, RwLock}};
 
-use alpha::AlphabeticParser;
+use alpha::{AlphabeticConfig, AlphabeticParser};
+use anyhow::anyhow;
+use async_stream::try_stream;
 use axum::async_trait;
+use cache::{files_version, parser_version, SegmentCache};
+use chinese::{ChineseConfig, ChineseParser};
+use external::{ExternalConfig, ExternalParser};
+use hanja::HanjaDict;
+use futures::{stream::BoxStream, Stream, StreamExt};
+use itertools::Itertools;
+use japanese::{JapaneseConfig, JapaneseParser};
 use korean::{KoreanConfig, KoreanParser};
-use serde::{Deserialize, Serialize};
+use phrase::PhraseTrie;
+use sentence::split_sentences;
+use serde::{de::{self, value::MapAccessDeserializer, MapAccess, Unexpected, Visitor}, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
 
-use crate::{dict::{Dictionary, Word}, doc::Document, Result};
+use crate::{bad_req, config::Config, dict::{track_checked_words, Dictionary, Word}, doc::Document, overrides::ParseOverride, Result};
 
 pub mod alpha;
+pub mod cache;
+pub mod chinese;
+pub mod external;
+pub mod hangul;
+pub mod hanja;
+pub mod japanese;
 pub mod korean;
+pub mod phrase;
+pub mod sentence;
 
+#[derive(Clone)]
 pub struct Segment {
     pub range: Range<usize>,
     pub text: String,
     pub words: Vec<Word>,
+    /// Whether the parser found that the segment ends a sentence (e.g., it
+    /// has a Korean sentence-final ending), regardless of punctuation.
+    pub sentence_end: bool,
 }
 
 impl Segment {
@@ -23,76 +47,425 @@ impl Segment {
     }
 }
 
+fn maybe_string_to_list(s: Option<String>) -> Vec<String> {
+    s
+        .filter(|p| !p.is_empty())
+        .map(|s| s.split(',').map(|t| t.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
 #[async_trait]
-pub trait Parser {
+pub trait Parser: Sync {
     async fn parse(&self, text: &str) -> Result<Vec<Segment>>;
+
+    /// Parses the text as a stream of segments, so that earlier segments may
+    /// be used before the whole text is parsed. By default, this just yields
+    /// the output of `parse`.
+    fn parse_stream<'a>(&'a self, text: &'a str) -> BoxStream<'a, Result<Segment>> {
+        try_stream! {
+            for seg in self.parse(text).await? {
+                yield seg;
+            }
+        }.boxed()
+    }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-#[serde(rename_all = "snake_case")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(remote = "Self", rename_all = "snake_case")]
 #[allow(clippy::large_enum_variant)]
 pub enum MorphConfig {
-    #[default]
-    Alphabetic,
+    Alphabetic(AlphabeticConfig),
+    Chinese(ChineseConfig),
+    External(ExternalConfig),
+    Japanese(JapaneseConfig),
     Korean(KoreanConfig),
 }
 
+/// Besides a `[morph.<parser>]` table, accepts `morph = "alphabetic"` for the
+/// alphabetic parser with its default options, as in configs written before
+/// it had any.
+impl<'de> Deserialize<'de> for MorphConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct MorphConfigVisitor;
+
+        impl<'de> Visitor<'de> for MorphConfigVisitor {
+            type Value = MorphConfig;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("\"alphabetic\" or a parser configuration")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> std::result::Result<MorphConfig, E> {
+                match name {
+                    "alphabetic" => Ok(MorphConfig::Alphabetic(AlphabeticConfig::default())),
+                    _ => Err(E::invalid_value(Unexpected::Str(name), &self)),
+                }
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> std::result::Result<MorphConfig, A::Error> {
+                MorphConfig::deserialize(MapAccessDeserializer::new(map))
+            }
+        }
+
+        deserializer.deserialize_any(MorphConfigVisitor)
+    }
+}
+
+impl Serialize for MorphConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        MorphConfig::serialize(self, serializer)
+    }
+}
+
+impl Default for MorphConfig {
+    fn default() -> Self {
+        Self::Alphabetic(AlphabeticConfig::default())
+    }
+}
+
+impl MorphConfig {
+    /// Returns the paths of the files loaded by the parser.
+    pub fn files(&self) -> Vec<PathBuf> {
+        match self {
+            Self::Alphabetic(cfg) => cfg.files(),
+            Self::Chinese(cfg) => cfg.files(),
+            Self::External(cfg) => cfg.files(),
+            Self::Japanese(cfg) => cfg.files(),
+            Self::Korean(cfg) => cfg.files(),
+        }
+    }
+
+    /// Returns the files that the parser loads again when it is reloaded (see
+    /// `Morph::reload`). Changes to its other files only take effect when LIT
+    /// is restarted.
+    pub fn reloadable_files(&self) -> Vec<PathBuf> {
+        match self {
+            Self::Korean(cfg) => cfg.reloadable_files(),
+            _ => vec![],
+        }
+    }
+}
+
 pub enum Morph {
-    Alphabetic,
+    Alphabetic(AlphabeticParser),
+    Chinese(ChineseParser),
+    External(ExternalParser),
+    Japanese(JapaneseParser),
     Korean(KoreanParser)
 }
 
 impl Morph {
     pub fn load(config: &MorphConfig, lit_dict: Dictionary) -> Result<Self> {
         Ok(match config {
-            MorphConfig::Alphabetic => Self::Alphabetic,
+            MorphConfig::Alphabetic(cfg) => Self::Alphabetic(AlphabeticParser::load(cfg, lit_dict)?),
+            MorphConfig::Chinese(cfg) => Self::Chinese(ChineseParser::load(cfg)?),
+            MorphConfig::External(cfg) => Self::External(ExternalParser::load(cfg)?),
+            MorphConfig::Japanese(cfg) => Self::Japanese(JapaneseParser::load(cfg)?),
             MorphConfig::Korean(cfg) => Self::Korean(KoreanParser::load(cfg, lit_dict)?),
         })
     }
+
+    /// Reloads the parser's rules, if it supports doing so. Returns whether
+    /// the parser was reloaded.
+    pub fn reload(&self) -> Result<bool> {
+        match self {
+            Self::Korean(p) => p.reload().map(|_| true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Whether the parser supports overrides entered from the reader.
+    pub fn supports_overrides(&self) -> bool {
+        matches!(self, Self::Korean(_))
+    }
+
+    /// Checks that `parsing` is a valid override for how `word` is parsed.
+    pub fn validate_override(&self, word: &str, parsing: &str) -> Result<()> {
+        match self {
+            Self::Korean(_) => korean::validate_parsing(word, parsing),
+            _ => bad_req("parse overrides are not supported for this language"),
+        }
+    }
+
+    /// Returns the Hanja dictionary, if the parser has one.
+    pub fn hanja(&self) -> Option<Arc<HanjaDict>> {
+        match self {
+            Self::Korean(p) => Some(p.hanja()),
+            _ => None,
+        }
+    }
+
+    /// Returns the names of the grammar points that the parser's rules refer
+    /// to.
+    pub fn grammar_points(&self) -> Vec<String> {
+        match self {
+            Self::Korean(p) => p.grammar_points(),
+            _ => vec![],
+        }
+    }
+
+    fn set_overrides(&self, overrides: &[ParseOverride]) {
+        if let Self::Korean(p) = self {
+            p.set_overrides(overrides);
+        }
+    }
 }
 
 #[async_trait]
 impl Parser for Morph {
     async fn parse(&self, text: &str) -> Result<Vec<Segment>> {
         match self {
-            Self::Alphabetic => AlphabeticParser.parse(text).await,
+            Self::Alphabetic(p) => p.parse(text).await,
+            Self::Chinese(p) => p.parse(text).await,
+            Self::External(p) => p.parse(text).await,
+            Self::Japanese(p) => p.parse(text).await,
             Self::Korean(p) => p.parse(text).await
         }
     }
+
+    fn parse_stream<'a>(&'a self, text: &'a str) -> BoxStream<'a, Result<Segment>> {
+        match self {
+            Self::Alphabetic(p) => p.parse_stream(text),
+            Self::Chinese(p) => p.parse_stream(text),
+            Self::External(p) => p.parse_stream(text),
+            Self::Japanese(p) => p.parse_stream(text),
+            Self::Korean(p) => p.parse_stream(text),
+        }
+    }
 }
 
-pub async fn analyze_document<P: Parser + ?Sized>(doc: Document, parser: &P, dict: &Dictionary) -> Result<Document> {
-    if doc.info::<BTreeMap<usize, Segment>>().is_some() {
-        return Ok(doc);
+/// Parsers for each configured language, keyed by language code.
+pub struct Morphs {
+    default: String,
+    morphs: HashMap<String, LoadedMorph>,
+    cache: SegmentCache,
+}
+
+struct LoadedMorph {
+    morph: Morph,
+    config: MorphConfig,
+    /// The version of the parser as it was loaded, apart from the files that
+    /// it reloads.
+    version: String,
+    /// The version of the files that the parser reloads, as last loaded.
+    reloaded_version: RwLock<String>,
+    overrides_version: RwLock<String>,
+}
+
+impl LoadedMorph {
+    fn reloaded_version(&self) -> String {
+        self.reloaded_version.read().unwrap_or_else(|e| e.into_inner()).clone()
     }
-    let mut segs = vec![];
 
-    for span in doc.spans.iter() {
-        let text = &doc.text[span.clone()];
-        if text.trim().is_empty() {
-            continue;
+    /// Returns the version used for cached segmentations, which also depends
+    /// on the reloaded files and the parse overrides.
+    fn cache_version(&self) -> String {
+        let version = format!("{}.{}", self.version, self.reloaded_version());
+        let overrides_version = self.overrides_version.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if overrides_version.is_empty() {
+            version
+        } else {
+            format!("{version}+{overrides_version}")
         }
-        for seg in parser.parse(text).await? {
-            let seg = seg.with_offset(span.start);
-            // if seg.words.is_empty() {
-            //     continue;
-            // }
+    }
+}
+
+impl Morphs {
+    pub fn load(config: &Config, lit_dict: Dictionary, cache: SegmentCache) -> Result<Self> {
+        let mut morphs = HashMap::new();
+        for (lang, lang_config) in config.languages.iter() {
+            let morph = Morph::load(&lang_config.morph, lit_dict.for_language(lang))?;
+            let version = parser_version(&lang_config.morph)?;
+            let reloaded_version = RwLock::new(files_version(&lang_config.morph.reloadable_files())?);
+            let overrides_version = RwLock::default();
+            morphs.insert(lang.clone(), LoadedMorph { morph, config: lang_config.morph.clone(), version, reloaded_version, overrides_version });
+        }
+        Ok(Morphs { default: config.lang.clone(), morphs, cache })
+    }
 
-            let mut words = dict.find_words_by_text(&seg.text).await?;
-            let dict_words_empty = words.is_empty();
-            for w in seg.words.iter() {
-                if !w.parents.contains(&w.text) && (dict_words_empty || !w.translation.is_empty()) {
-                    words.push(w.clone());
+    fn get_loaded(&self, lang: Option<&str>) -> Result<&LoadedMorph> {
+        let lang = lang.unwrap_or(&self.default);
+        self.morphs.get(lang)
+            .ok_or_else(|| anyhow!("no parser for language: {lang}").into())
+    }
+
+    /// Returns the parser for the given language, or for the default language
+    /// if none is given.
+    pub fn get(&self, lang: Option<&str>) -> Result<&Morph> {
+        Ok(&self.get_loaded(lang)?.morph)
+    }
+
+    /// Returns the given language code, or the default language if none is
+    /// given.
+    pub fn language<'a>(&'a self, lang: Option<&'a str>) -> &'a str {
+        lang.unwrap_or(&self.default)
+    }
+
+    /// Returns the codes of the languages that have parsers.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.morphs.keys().map(|lang| lang.as_str())
+    }
+
+    /// Replaces the parse overrides for the given language.
+    pub fn set_overrides(&self, lang: &str, overrides: &[ParseOverride]) -> Result<()> {
+        let loaded = self.get_loaded(Some(lang))?;
+        loaded.morph.set_overrides(overrides);
+        let mut hash = Sha256::new();
+        for ovr in overrides {
+            hash.update(ovr.word.as_bytes());
+            hash.update([0]);
+            hash.update(ovr.parsing.as_bytes());
+            hash.update([0]);
+        }
+        let version = if overrides.is_empty() { String::new() } else { format!("{:x}", hash.finalize()) };
+        *loaded.overrides_version.write().unwrap_or_else(|e| e.into_inner()) = version;
+        Ok(())
+    }
+
+    /// Reloads the rules of each parser that supports it. Unless `force` is
+    /// set, only parsers whose rule files have changed since they were loaded
+    /// are reloaded. Returns the languages whose parsers were reloaded.
+    pub fn reload(&self, force: bool) -> Result<Vec<String>> {
+        let mut reloaded = vec![];
+        for (lang, loaded) in self.morphs.iter() {
+            let version = files_version(&loaded.config.reloadable_files())?;
+            if !force && version == loaded.reloaded_version() {
+                continue;
+            }
+            if loaded.morph.reload()? {
+                *loaded.reloaded_version.write().unwrap_or_else(|e| e.into_inner()) = version;
+                reloaded.push(lang.clone());
+            }
+        }
+        reloaded.sort();
+        Ok(reloaded)
+    }
+
+    /// Like `analyze_stream`, but for the text of a book in the given
+    /// language, with `dict` limited to that language. The segmentation is
+    /// loaded from the cache if the book text, parser and the dictionary words
+    /// the parser checked are unchanged since it was last parsed, and is saved
+    /// to the cache otherwise.
+    pub fn analyze_book_stream<'a>(&'a self, book_id: i64, lang: Option<&'a str>, text: &'a str, spans: &'a [Range<usize>], dict: &'a Dictionary) -> impl Stream<Item = Result<Segment>> + Send + 'a {
+        try_stream! {
+            let loaded = self.get_loaded(lang)?;
+            let (parser, version) = (&loaded.morph, loaded.cache_version());
+            let key = SegmentCache::key(&version, text, spans);
+            if let Some(segs) = self.cache.get(book_id, &key, dict).await? {
+                for seg in segs {
+                    yield lookup_words(seg, dict).await?;
+                }
+            } else {
+                let mut segs = vec![];
+                let mut checked = BTreeMap::new();
+                let mut stream = Box::pin(parse_spans(text, spans, parser));
+                loop {
+                    let (seg, seg_checked) = track_checked_words(stream.next()).await;
+                    checked.extend(seg_checked);
+                    let Some(seg) = seg else {
+                        break;
+                    };
+                    let seg = seg?;
+                    segs.push(seg.clone());
+                    yield lookup_words(seg, dict).await?;
                 }
+                self.cache.put(book_id, &key, &segs, &checked).await?;
             }
-            let seg = Segment { words: words.clone(), ..seg };
+        }
+    }
 
-            segs.push(seg);
+    /// Like `analyze_document`, but for the text of a book in the given
+    /// language, using the cached segmentation if possible (see
+    /// `analyze_book_stream`).
+    pub async fn analyze_book(&self, book_id: i64, lang: Option<&str>, doc: Document, dict: &Dictionary) -> Result<Document> {
+        if doc.info::<BTreeMap<usize, Segment>>().is_some() {
+            return Ok(doc);
+        }
+        let mut segs = BTreeMap::new();
+        {
+            let mut stream = Box::pin(self.analyze_book_stream(book_id, lang, &doc.text, &doc.spans, dict));
+            while let Some(seg) = stream.next().await {
+                let seg = seg?;
+                segs.insert(seg.range.start, seg);
+            }
         }
+        annotate(doc.with(segs), dict).await
     }
+}
 
-    let segs = BTreeMap::from_iter(
-        segs.into_iter().map(|seg| (seg.range.start, seg)));
+async fn lookup_words(seg: Segment, dict: &Dictionary) -> Result<Segment> {
+    let mut words = dict.find_words_by_text(&seg.text).await?;
+    let dict_words_empty = words.is_empty();
+    // Hanja isn't stored in the dictionary, so use the parser's.
+    if let Some(hanja) = seg.words.iter().find_map(|w| w.hanja.clone()) {
+        for w in words.iter_mut().filter(|w| w.hanja.is_none()) {
+            w.hanja = Some(hanja.clone());
+        }
+    }
+    // Likewise for grammar points, which depend on how the text was parsed.
+    let grammar = seg.words.iter()
+        .flat_map(|w| w.grammar.iter())
+        .unique()
+        .cloned()
+        .collect_vec();
+    for w in words.iter_mut().filter(|w| w.grammar.is_empty()) {
+        w.grammar.clone_from(&grammar);
+    }
+    for w in seg.words.iter() {
+        if !w.parents.contains(&w.text) && (dict_words_empty || !w.translation.is_empty()) {
+            words.push(w.clone());
+        }
+    }
+    Ok(Segment { words, ..seg })
+}
+
+/// Streams the parser output for the given spans of `text` in order, without
+/// looking up any words in the dictionary.
+pub fn parse_spans<'a, P: Parser + ?Sized>(text: &'a str, spans: &'a [Range<usize>], parser: &'a P) -> impl Stream<Item = Result<Segment>> + Send + 'a {
+    try_stream! {
+        for span in spans.iter() {
+            let span_text = &text[span.clone()];
+            if span_text.trim().is_empty() {
+                continue;
+            }
+            let mut segs = parser.parse_stream(span_text);
+            while let Some(seg) = segs.next().await {
+                yield seg?.with_offset(span.start);
+            }
+        }
+    }
+}
+
+/// Streams the segments of the given spans of `text` in order, with the words
+/// for each segment looked up in the dictionary.
+pub fn analyze_stream<'a, P: Parser + ?Sized>(text: &'a str, spans: &'a [Range<usize>], parser: &'a P, dict: &'a Dictionary) -> impl Stream<Item = Result<Segment>> + Send + 'a {
+    try_stream! {
+        let mut segs = Box::pin(parse_spans(text, spans, parser));
+        while let Some(seg) = segs.next().await {
+            yield lookup_words(seg?, dict).await?;
+        }
+    }
+}
+
+pub async fn analyze_document<P: Parser + ?Sized>(doc: Document, parser: &P, dict: &Dictionary) -> Result<Document> {
+    if doc.info::<BTreeMap<usize, Segment>>().is_some() {
+        return Ok(doc);
+    }
+    let mut segs = BTreeMap::new();
+    {
+        let mut stream = Box::pin(analyze_stream(&doc.text, &doc.spans, parser, dict));
+        while let Some(seg) = stream.next().await {
+            let seg = seg?;
+            segs.insert(seg.range.start, seg);
+        }
+    }
+    annotate(doc.with(segs), dict).await
+}
 
-    Ok(doc.with(segs))
+/// Adds the multi-word dictionary entries (see `phrase::Phrases`) and the
+/// sentences (see `sentence::Sentences`) found in an analyzed document.
+async fn annotate(doc: Document, dict: &Dictionary) -> Result<Document> {
+    let phrases = PhraseTrie::load(dict).await?.find_in_document(&doc, dict).await?;
+    let sentences = split_sentences(&doc);
+    Ok(doc.with(phrases).with(sentences))
 }
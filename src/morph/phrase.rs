@@ -0,0 +1,106 @@
+use std::{collections::{BTreeMap, HashMap}, ops::Range};
+
+use crate::{dict::Dictionary, doc::Document, Result};
+
+use super::Segment;
+
+/// Multi-word expressions found in a document, keyed by start offset. The
+/// segments making up each phrase remain in the document's
+/// `BTreeMap<usize, Segment>`.
+#[derive(Clone, Default)]
+pub struct Phrases(pub BTreeMap<usize, Segment>);
+
+/// Trie of the space-separated parts of dictionary entries containing spaces.
+#[derive(Default)]
+pub struct PhraseTrie {
+    children: HashMap<String, PhraseTrie>,
+    phrase: Option<String>,
+}
+
+impl PhraseTrie {
+    pub fn new<I, S>(phrases: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut trie = PhraseTrie::default();
+        for phrase in phrases {
+            let phrase = phrase.as_ref();
+            if phrase.split_whitespace().nth(1).is_none() {
+                continue;
+            }
+            let mut node = &mut trie;
+            for part in phrase.split_whitespace() {
+                node = node.children.entry(part.to_string()).or_default();
+            }
+            node.phrase = Some(phrase.to_string());
+        }
+        trie
+    }
+
+    /// Loads the multi-word entries in the dictionary.
+    pub async fn load(dict: &Dictionary) -> Result<Self> {
+        Ok(Self::new(dict.find_phrases().await?))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Finds phrases made up of consecutive segments within `range` of `text`,
+    /// separated only by whitespace. Phrases are matched from left to right,
+    /// preferring the longest match, and don't overlap.
+    pub async fn find(&self, text: &str, segs: &BTreeMap<usize, Segment>, range: Range<usize>, dict: &Dictionary) -> Result<Vec<Segment>> {
+        let segs: Vec<&Segment> = segs.range(range.clone())
+            .map(|(_, seg)| seg)
+            .filter(|seg| seg.range.end <= range.end)
+            .collect();
+        let mut phrases = vec![];
+        let mut i = 0;
+        while i < segs.len() {
+            let mut node = self;
+            let mut best = None;
+            for j in i..segs.len() {
+                if j > i && !text[segs[j - 1].range.end..segs[j].range.start].chars().all(char::is_whitespace) {
+                    break;
+                }
+                let Some(child) = node.children.get(&segs[j].text) else {
+                    break;
+                };
+                node = child;
+                if let Some(ref phrase) = node.phrase {
+                    best = Some((j, phrase));
+                }
+            }
+            let Some((j, phrase)) = best else {
+                i += 1;
+                continue;
+            };
+            phrases.push(Segment {
+                range: segs[i].range.start..segs[j].range.end,
+                text: phrase.clone(),
+                words: dict.find_words_by_text(phrase).await?,
+                sentence_end: segs[j].sentence_end,
+            });
+            i = j + 1;
+        }
+        Ok(phrases)
+    }
+
+    /// Finds the phrases in each span of an analyzed document.
+    pub async fn find_in_document(&self, doc: &Document, dict: &Dictionary) -> Result<Phrases> {
+        let mut phrases = Phrases::default();
+        let Some(segs) = doc.info::<BTreeMap<usize, Segment>>() else {
+            return Ok(phrases);
+        };
+        if self.is_empty() {
+            return Ok(phrases);
+        }
+        for span in doc.spans.iter() {
+            for phrase in self.find(&doc.text, segs, span.clone(), dict).await? {
+                phrases.0.insert(phrase.range.start, phrase);
+            }
+        }
+        Ok(phrases)
+    }
+}
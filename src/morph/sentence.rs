@@ -0,0 +1,97 @@
+use std::{borrow::Cow, collections::BTreeMap, ops::Range};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::doc::{vtt::Cue, Document};
+
+use super::Segment;
+
+/// The sentences of a document, as non-overlapping ranges of its text in
+/// order. Sentences never cross span boundaries, nor the end of a VTT cue that
+/// the next cue doesn't overlap.
+#[derive(Clone, Debug, Default)]
+pub struct Sentences(pub Vec<Range<usize>>);
+
+impl Sentences {
+    /// Returns the sentence containing the given offset into the text.
+    pub fn containing(&self, offset: usize) -> Option<Range<usize>> {
+        let i = self.0.partition_point(|s| s.end <= offset);
+        self.0.get(i)
+            .filter(|s| s.start <= offset)
+            .cloned()
+    }
+
+    /// Returns the sentences overlapping the given range of the text.
+    pub fn overlapping(&self, range: Range<usize>) -> &[Range<usize>] {
+        let start = self.0.partition_point(|s| s.end <= range.start);
+        let end = self.0.partition_point(|s| s.start < range.end);
+        &self.0[start..end.max(start)]
+    }
+}
+
+/// Characters that may follow the end of a sentence and still belong to it,
+/// e.g., closing quotes after a Korean sentence-final ending.
+fn is_trailing_punctuation(c: char) -> bool {
+    !c.is_whitespace() && !c.is_alphanumeric()
+}
+
+/// Splits the spans of a document into sentences. Sentences end at
+/// sentence-final punctuation and line breaks (per Unicode text segmentation),
+/// and after segments that the parser found to end a sentence, if the document
+/// has been analyzed. In a VTT document, line breaks only wrap the text of a
+/// cue, so sentences end at the end of each cue that the next cue doesn't
+/// overlap instead.
+pub fn split_sentences(doc: &Document) -> Sentences {
+    let segs = doc.info::<BTreeMap<usize, Segment>>();
+    let cues = doc.info::<Vec<Cue>>();
+    let mut sentences = vec![];
+    for span in doc.spans.iter() {
+        let text = match cues {
+            Some(_) => Cow::Owned(doc.text[span.clone()].replace('\n', " ")),
+            None => Cow::Borrowed(&doc.text[span.clone()]),
+        };
+        let mut bounds: Vec<usize> = text.split_sentence_bound_indices()
+            .map(|(i, _)| span.start + i)
+            .collect();
+        for pair in cues.iter().flat_map(|cues| cues.windows(2)) {
+            let end = pair[0].text_range.end;
+            if pair[1].text_range.start >= end && span.start < end && end < span.end {
+                bounds.push(end);
+            }
+        }
+        for (_, seg) in segs.iter().flat_map(|segs| segs.range(span.clone())) {
+            if !seg.sentence_end || seg.range.end > span.end {
+                continue;
+            }
+            let rest = &doc.text[seg.range.end..span.end];
+            let trailing = rest.char_indices()
+                .find(|(_, c)| !is_trailing_punctuation(*c))
+                .map(|(i, _)| i)
+                .unwrap_or(rest.len());
+            // E.g., "했다"고 is a quotation, not the end of a sentence.
+            if rest[trailing..].chars().next().is_some_and(|c| !c.is_whitespace()) {
+                continue;
+            }
+            bounds.push(seg.range.end + trailing);
+        }
+        bounds.push(span.end);
+        bounds.sort();
+        bounds.dedup();
+
+        let mut start = span.start;
+        for end in bounds {
+            if end <= start {
+                continue;
+            }
+            let sentence = &doc.text[start..end];
+            let trimmed = sentence.trim_start();
+            let s = start + (sentence.len() - trimmed.len());
+            let e = s + trimmed.trim_end().len();
+            if s < e {
+                sentences.push(s..e);
+            }
+            start = end;
+        }
+    }
+    Sentences(sentences)
+}
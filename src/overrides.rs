@@ -0,0 +1,84 @@
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+
+use crate::{must, not_found, Result};
+
+/// A user-entered correction to how a word is parsed. The format of `parsing`
+/// depends on the parser for the language (see `Morph::validate_override`).
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct ParseOverride {
+    pub id: i64,
+    pub language: String,
+    pub word: String,
+    pub parsing: String,
+}
+
+#[derive(Clone)]
+pub struct ParseOverrides {
+    db: Pool<Sqlite>,
+}
+
+impl ParseOverrides {
+    pub fn new(db: Pool<Sqlite>) -> Self {
+        Self { db }
+    }
+
+    /// Returns the overrides for the given language, optionally only those for
+    /// the given word.
+    pub async fn list(&self, language: &str, word: Option<&str>) -> Result<Vec<ParseOverride>> {
+        let overrides = sqlx::query_as("
+            SELECT id, language, word, parsing
+            FROM parse_override
+            WHERE language = ? AND (? IS NULL OR word = ?)
+            ORDER BY id
+            ")
+            .bind(language)
+            .bind(word)
+            .bind(word)
+            .fetch_all(&self.db)
+            .await?;
+        Ok(overrides)
+    }
+
+    pub async fn find_by_id(&self, id: i64) -> Result<ParseOverride> {
+        let ovr = must(sqlx::query_as("SELECT id, language, word, parsing FROM parse_override WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?)?;
+        Ok(ovr)
+    }
+
+    pub async fn insert(&self, language: &str, word: &str, parsing: &str) -> Result<i64> {
+        let result = sqlx::query("INSERT INTO parse_override (language, word, parsing) VALUES (?, ?, ?)")
+            .bind(language)
+            .bind(word)
+            .bind(parsing)
+            .execute(&self.db)
+            .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn update(&self, id: i64, word: &str, parsing: &str) -> Result<()> {
+        let result = sqlx::query("UPDATE parse_override SET word = ?, parsing = ? WHERE id = ?")
+            .bind(word)
+            .bind(parsing)
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() != 1 {
+            not_found()?;
+        }
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<()> {
+        let result = sqlx::query("DELETE FROM parse_override WHERE id = ?")
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() != 1 {
+            not_found()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use std::{cmp::Ordering, collections::{BTreeMap, VecDeque}};
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{check, dict::{Dictionary, WordStatus}, doc::Document, morph::{sentence::Sentences, Segment}, Result};
+
+/// What to look for when searching for example sentences.
+#[derive(Clone, Debug)]
+pub enum SearchTarget {
+    /// The given word, or any word derived from it.
+    Word(String),
+    /// Words whose parse applied a rule with the given grammar point.
+    Grammar(String),
+    /// Words with the given tag, e.g., one added by a term pattern rule.
+    Tag(String),
+}
+
+impl SearchTarget {
+    /// Returns whether the segment is (or contains) the target.
+    pub async fn matches(&self, seg: &Segment, dict: &Dictionary) -> Result<bool> {
+        match self {
+            Self::Word(text) => {
+                if seg.text == *text {
+                    return Ok(true);
+                }
+                let mut all_parents = vec![];
+                for word in seg.words.iter() {
+                    all_parents.extend(word.parents.clone());
+                }
+                Ok(dict.find_word_trees_by_text(all_parents).await?.contains_key(text))
+            },
+            Self::Grammar(name) => Ok(seg.words.iter().any(|w| w.grammar.contains(name))),
+            Self::Tag(tag) => Ok(seg.words.iter().any(|w| w.tags.contains(tag))),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchParams {
+    pub target: SearchTarget,
+    pub min_sentence_words: usize,
+    pub max_sentence_words: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Sentence {
+    pub words: Vec<SentenceWord>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SentenceWord {
+    pub text: String,
+    pub status: WordStatus,
+    pub is_target: bool,
+}
+
+fn min_non_target_status(s: &Sentence) -> Option<WordStatus> {
+    let mut status = None;
+    for word in s.words.iter().filter(|w| !w.is_target) {
+        status = match (status, word.status) {
+            (st, WordStatus::Ignored) => st,
+            (None, st) => Some(st),
+            (Some(a), b) => Some(a.min(b))
+        }
+    }
+    status
+}
+
+/// Orders sentences by how easy they are to read, ignoring the target words:
+/// the sentence with the better-known least-known word is greater, then the
+/// one with fewer words at each status from unknown up, then the longer one.
+pub fn compare_readability(a: &Sentence, b: &Sentence) -> Ordering {
+    match (min_non_target_status(a), min_non_target_status(b)) {
+        (None, None) => return Ordering::Equal,
+        (Some(_), None) => return Ordering::Greater,
+        (None, Some(_)) => return Ordering::Less,
+        (Some(x), Some(y)) if x == y => x,
+        (Some(x), Some(y)) => return x.cmp(&y),
+    };
+
+    for status in [WordStatus::Unknown, WordStatus::New, WordStatus::Level2, WordStatus::Level3, WordStatus::Level4, WordStatus::Level5, WordStatus::WellKnown] {
+        let a_count = a.words.iter().filter(|w| !w.is_target && w.status == status).count();
+        let b_count = b.words.iter().filter(|w| !w.is_target && w.status == status).count();
+        if a_count < b_count {
+            return Ordering::Greater;
+        } else if a_count > b_count {
+            return Ordering::Less;
+        }
+    }
+
+    a.words.len().cmp(&b.words.len())
+}
+
+/// Finds the sentences of an analyzed document containing the target. Long
+/// sentences are cut down to `max_sentence_words` words around the target.
+pub async fn find_sentences(params: &SearchParams, dict: &Dictionary, doc: &Document) -> Result<Vec<Sentence>> {
+    let words: &BTreeMap<usize, Segment> = doc.info()
+        .ok_or_else(|| anyhow!("could not analyze document"))?;
+    let Sentences(doc_sentences) = doc.info()
+        .ok_or_else(|| anyhow!("could not split document into sentences"))?;
+    let (min_sentence_words, max_sentence_words) = (params.min_sentence_words, params.max_sentence_words);
+    check(max_sentence_words >= 1, "sentences must be allowed at least one word")?;
+    let mut sentences = vec![];
+
+    for sentence in doc_sentences.iter() {
+        let mut context_words = VecDeque::new();
+        for (_, seg) in words.range(sentence.clone()) {
+            let (_, optimistic_rating) =
+                dict.resolve_stati(seg.words.iter()).await
+                .unwrap_or((WordStatus::Unknown, WordStatus::Unknown));
+            context_words.push_back(SentenceWord {
+                text: seg.text.to_string(),
+                status: optimistic_rating,
+                is_target: params.target.matches(seg, dict).await?,
+            });
+            if context_words.len() > max_sentence_words {
+                context_words.pop_front();
+                let mid = max_sentence_words / 2;
+                if context_words[mid].is_target { // FIXME: off-by-one?
+                    let words = context_words.iter().cloned().collect_vec();
+                    sentences.push(Sentence { words });
+                }
+            } else if context_words.len() == max_sentence_words {
+                let mid = max_sentence_words / 2;
+                if context_words.iter().take(mid).any(|w| w.is_target) { // FIXME: off-by-one?
+                    let words = context_words.iter().cloned().collect_vec();
+                    sentences.push(Sentence { words });
+                }
+            }
+        }
+
+        if context_words.len() < max_sentence_words {
+            if context_words.len() >= min_sentence_words && context_words.iter().any(|w| w.is_target) {
+                let words = context_words.iter().cloned().collect_vec();
+                sentences.push(Sentence { words });
+            }
+        } else {
+            let mid = max_sentence_words / 2;
+            if context_words.iter().dropping(mid).any(|w| w.is_target) { // FIXME: off-by-one?
+                let words = context_words.iter().cloned().collect_vec();
+                sentences.push(Sentence { words });
+            }
+        }
+    }
+
+    Ok(sentences)
+}